@@ -1,33 +1,131 @@
+use swc_common::{Mark, Span, SyntaxContext};
 use swc_ecmascript::{
     ast::*,
-    visit::{Visit, VisitWith},
+    transforms::resolver_with_mark,
+    visit::{FoldWith, Visit, VisitWith},
 };
 
-pub(crate) fn contains_cjs(m: &Module) -> bool {
+/// Module system used by a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ModuleKind {
+    /// `import` / `export`, or no module syntax at all.
+    Esm,
+    /// `module.exports` or `exports.foo`, or `require()` without ESM syntax.
+    Cjs,
+    /// ESM syntax together with CommonJS exports. `span` points to the first
+    /// CommonJS export.
+    Mixed { span: Span },
+}
+
+/// Must be called while `GLOBALS` is set, because the program is resolved to
+/// tell the globals `module`, `exports` and `require` from variables which
+/// shadow them.
+pub(crate) fn module_kind(program: &Program) -> ModuleKind {
+    // Before resolving, every identifier looks like a global. Most files don't
+    // use any of them, so the program is only cloned and resolved if the first
+    // visit finds something.
     let mut v = CjsFinder::default();
-    m.visit_with(&mut v);
-    v.found
+    program.visit_with(&mut v);
+    if v.exports.is_some() || v.requires {
+        let resolved = program
+            .clone()
+            .fold_with(&mut resolver_with_mark(Mark::fresh(Mark::root())));
+        v = CjsFinder::default();
+        resolved.visit_with(&mut v);
+    }
+
+    let has_esm = match program {
+        Program::Module(m) => m
+            .body
+            .iter()
+            .any(|item| matches!(item, ModuleItem::ModuleDecl(..))),
+        Program::Script(..) => false,
+    };
+
+    match v.exports {
+        Some(span) if has_esm => ModuleKind::Mixed { span },
+        Some(..) => ModuleKind::Cjs,
+        // `require()` is also used in ES modules which are bundled by webpack,
+        // so it only makes a file without ESM syntax CommonJS.
+        None if v.requires && !has_esm => ModuleKind::Cjs,
+        None => ModuleKind::Esm,
+    }
 }
 
 #[derive(Copy, Clone, Default)]
 struct CjsFinder {
-    found: bool,
+    /// Span of the first CommonJS export.
+    exports: Option<Span>,
+    /// The file calls `require()`.
+    requires: bool,
+}
+
+impl CjsFinder {
+    fn found_export(&mut self, span: Span) {
+        self.exports.get_or_insert(span);
+    }
+}
+
+/// Returns true if `e` is the global `sym`. The resolver leaves the context of
+/// identifiers which are not declared in the file empty.
+fn is_global(e: &Expr, sym: &str) -> bool {
+    matches!(e, Expr::Ident(i) if &*i.sym == sym && i.span.ctxt == SyntaxContext::empty())
+}
+
+fn prop_name(prop: &MemberProp) -> Option<&str> {
+    match prop {
+        MemberProp::Ident(i) => Some(&*i.sym),
+        MemberProp::Computed(ComputedPropName { expr, .. }) => match &**expr {
+            Expr::Lit(Lit::Str(s)) => Some(&*s.value),
+            _ => None,
+        },
+        _ => None,
+    }
 }
 
 /// This visitor implementation supports typescript, because the api of `swc`
 /// does not support changing configuration based on content of the file.
 impl Visit for CjsFinder {
     fn visit_member_expr(&mut self, e: &MemberExpr) {
-        if let Expr::Ident(obj) = &*e.obj {
-            if let MemberProp::Ident(prop) = &e.prop {
-                if &*obj.sym == "module" && &*prop.sym == "exports" {
-                    self.found = true;
-                    return;
-                }
-            }
+        // `module.exports`, `module["exports"]`
+        if is_global(&e.obj, "module") && prop_name(&e.prop) == Some("exports") {
+            self.found_export(e.span);
+            return;
+        }
+
+        // `exports.foo`, `exports["foo"]`
+        if is_global(&e.obj, "exports") {
+            self.found_export(e.span);
         }
 
         e.obj.visit_with(self);
         e.prop.visit_with(self);
     }
+
+    fn visit_call_expr(&mut self, e: &CallExpr) {
+        if let Callee::Expr(callee) = &e.callee {
+            if is_global(callee, "require") {
+                self.requires = true;
+            }
+
+            // `Object.defineProperty(exports, ...)`
+            if let Expr::Member(MemberExpr {
+                obj,
+                prop: MemberProp::Ident(prop),
+                ..
+            }) = &**callee
+            {
+                if is_global(obj, "Object")
+                    && &*prop.sym == "defineProperty"
+                    && e.args.first().map_or(false, |arg| {
+                        arg.spread.is_none() && is_global(&arg.expr, "exports")
+                    })
+                {
+                    self.found_export(e.span);
+                }
+            }
+        }
+
+        e.visit_children_with(self);
+    }
 }
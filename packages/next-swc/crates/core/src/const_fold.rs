@@ -0,0 +1,292 @@
+use swc_atoms::JsWord;
+use swc_common::{SyntaxContext, DUMMY_SP};
+use swc_ecmascript::ast::*;
+use swc_ecmascript::utils::find_ids;
+use swc_ecmascript::visit::{noop_visit_type, Fold, FoldWith, Visit, VisitWith};
+
+/// Folds conditions which only involve literals, like `"production" !==
+/// "production"`, and removes the branches they make unreachable.
+///
+/// Only expressions without side effects are evaluated, so the output behaves
+/// like the input.
+pub(crate) fn const_fold() -> impl Fold {
+    ConstFolder
+}
+
+/// Value of an expression which can be computed at build time.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Const {
+    Str(JsWord),
+    Num(f64),
+    Bool(bool),
+    Null,
+    Undefined,
+}
+
+impl Const {
+    pub fn truthy(&self) -> bool {
+        match self {
+            Const::Str(s) => !s.is_empty(),
+            Const::Num(n) => *n != 0.0 && !n.is_nan(),
+            Const::Bool(b) => *b,
+            Const::Null | Const::Undefined => false,
+        }
+    }
+
+    fn type_of(&self) -> &'static str {
+        match self {
+            Const::Str(..) => "string",
+            Const::Num(..) => "number",
+            Const::Bool(..) => "boolean",
+            Const::Null => "object",
+            Const::Undefined => "undefined",
+        }
+    }
+
+    fn is_nullish(&self) -> bool {
+        matches!(self, Const::Null | Const::Undefined)
+    }
+
+    fn strict_eq(&self, other: &Const) -> bool {
+        self == other
+    }
+
+    /// `==`, for the cases which do not need a type conversion.
+    fn loose_eq(&self, other: &Const) -> Option<bool> {
+        match (self, other) {
+            (a, b) if a.is_nullish() || b.is_nullish() => Some(a.is_nullish() && b.is_nullish()),
+            (Const::Str(..), Const::Str(..))
+            | (Const::Num(..), Const::Num(..))
+            | (Const::Bool(..), Const::Bool(..)) => Some(self == other),
+            _ => None,
+        }
+    }
+}
+
+/// Evaluates `e` if it's a literal or an operation on literals.
+pub(crate) fn eval(e: &Expr) -> Option<Const> {
+    match e {
+        Expr::Lit(Lit::Str(s)) => Some(Const::Str(s.value.clone())),
+        Expr::Lit(Lit::Num(n)) => Some(Const::Num(n.value)),
+        Expr::Lit(Lit::Bool(b)) => Some(Const::Bool(b.value)),
+        Expr::Lit(Lit::Null(..)) => Some(Const::Null),
+        Expr::Ident(i) if &*i.sym == "undefined" && i.span.ctxt == SyntaxContext::empty() => {
+            Some(Const::Undefined)
+        }
+        Expr::Paren(ParenExpr { expr, .. }) => eval(expr),
+        Expr::Unary(UnaryExpr { op, arg, .. }) => {
+            let arg = eval(arg)?;
+            match op {
+                UnaryOp::Bang => Some(Const::Bool(!arg.truthy())),
+                UnaryOp::TypeOf => Some(Const::Str(arg.type_of().into())),
+                UnaryOp::Void => Some(Const::Undefined),
+                _ => None,
+            }
+        }
+        Expr::Bin(BinExpr {
+            op, left, right, ..
+        }) => {
+            let left = eval(left)?;
+            match op {
+                BinaryOp::LogicalAnd if !left.truthy() => Some(left),
+                BinaryOp::LogicalOr if left.truthy() => Some(left),
+                BinaryOp::NullishCoalescing if !left.is_nullish() => Some(left),
+                BinaryOp::LogicalAnd | BinaryOp::LogicalOr | BinaryOp::NullishCoalescing => {
+                    eval(right)
+                }
+                BinaryOp::EqEqEq => Some(Const::Bool(left.strict_eq(&eval(right)?))),
+                BinaryOp::NotEqEq => Some(Const::Bool(!left.strict_eq(&eval(right)?))),
+                BinaryOp::EqEq => left.loose_eq(&eval(right)?).map(Const::Bool),
+                BinaryOp::NotEq => left.loose_eq(&eval(right)?).map(|eq| Const::Bool(!eq)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn to_expr(value: Const) -> Expr {
+    match value {
+        Const::Str(value) => Expr::Lit(Lit::Str(Str {
+            span: DUMMY_SP,
+            value,
+            has_escape: false,
+            kind: Default::default(),
+        })),
+        Const::Num(value) => Expr::Lit(Lit::Num(Number {
+            span: DUMMY_SP,
+            value,
+        })),
+        Const::Bool(value) => Expr::Lit(Lit::Bool(Bool {
+            span: DUMMY_SP,
+            value,
+        })),
+        Const::Null => Expr::Lit(Lit::Null(Null { span: DUMMY_SP })),
+        Const::Undefined => Expr::Unary(UnaryExpr {
+            span: DUMMY_SP,
+            op: UnaryOp::Void,
+            arg: Box::new(Expr::Lit(Lit::Num(Number {
+                span: DUMMY_SP,
+                value: 0.0,
+            }))),
+        }),
+    }
+}
+
+struct ConstFolder;
+
+impl Fold for ConstFolder {
+    fn fold_expr(&mut self, e: Expr) -> Expr {
+        let e = e.fold_children_with(self);
+
+        match e {
+            Expr::Cond(CondExpr {
+                span,
+                test,
+                cons,
+                alt,
+            }) => match eval(&test) {
+                Some(test) if test.truthy() => *cons,
+                Some(..) => *alt,
+                None => Expr::Cond(CondExpr {
+                    span,
+                    test,
+                    cons,
+                    alt,
+                }),
+            },
+            // `a && b` is `b` if `a` is truthy, even if `b` is not a constant.
+            Expr::Bin(BinExpr {
+                span,
+                op: op @ (BinaryOp::LogicalAnd | BinaryOp::LogicalOr | BinaryOp::NullishCoalescing),
+                left,
+                right,
+            }) => match eval(&left) {
+                Some(value) => {
+                    let use_left = match op {
+                        BinaryOp::LogicalAnd => !value.truthy(),
+                        BinaryOp::LogicalOr => value.truthy(),
+                        _ => !value.is_nullish(),
+                    };
+                    if use_left {
+                        *left
+                    } else {
+                        *right
+                    }
+                }
+                None => Expr::Bin(BinExpr {
+                    span,
+                    op,
+                    left,
+                    right,
+                }),
+            },
+            Expr::Unary(..) | Expr::Bin(..) => match eval(&e) {
+                Some(value) => to_expr(value),
+                None => e,
+            },
+            _ => e,
+        }
+    }
+
+    fn fold_stmt(&mut self, s: Stmt) -> Stmt {
+        let s = s.fold_children_with(self);
+
+        match s {
+            Stmt::If(IfStmt {
+                span,
+                test,
+                cons,
+                alt,
+            }) => match eval(&test) {
+                Some(test) if test.truthy() => keep_hoisted_vars(*cons, alt.as_deref()),
+                Some(..) => match alt {
+                    Some(alt) => keep_hoisted_vars(*alt, Some(&cons)),
+                    None => keep_hoisted_vars(Stmt::Empty(EmptyStmt { span }), Some(&cons)),
+                },
+                None => Stmt::If(IfStmt {
+                    span,
+                    test,
+                    cons,
+                    alt,
+                }),
+            },
+            _ => s,
+        }
+    }
+
+    fn fold_stmts(&mut self, stmts: Vec<Stmt>) -> Vec<Stmt> {
+        let mut stmts = stmts.fold_children_with(self);
+        stmts.retain(|s| !matches!(s, Stmt::Empty(..)));
+        stmts
+    }
+
+    fn fold_module_items(&mut self, items: Vec<ModuleItem>) -> Vec<ModuleItem> {
+        let mut items = items.fold_children_with(self);
+        items.retain(|item| !matches!(item, ModuleItem::Stmt(Stmt::Empty(..))));
+        items
+    }
+}
+
+/// Returns `kept`, preceded by the `var` declarations of the `removed` branch
+/// without their initializers, as they are hoisted out of it.
+fn keep_hoisted_vars(kept: Stmt, removed: Option<&Stmt>) -> Stmt {
+    let mut finder = VarFinder::default();
+    if let Some(removed) = removed {
+        removed.visit_with(&mut finder);
+    }
+    if finder.ids.is_empty() {
+        return kept;
+    }
+
+    let decl = Stmt::Decl(Decl::Var(VarDecl {
+        span: DUMMY_SP,
+        kind: VarDeclKind::Var,
+        declare: false,
+        decls: finder
+            .ids
+            .into_iter()
+            .map(|id| VarDeclarator {
+                span: DUMMY_SP,
+                name: Pat::Ident(id.into()),
+                init: None,
+                definite: false,
+            })
+            .collect(),
+    }));
+
+    match kept {
+        Stmt::Empty(..) => decl,
+        kept => Stmt::Block(BlockStmt {
+            span: DUMMY_SP,
+            stmts: vec![decl, kept],
+        }),
+    }
+}
+
+/// Finds the names declared with `var` outside of functions.
+#[derive(Default)]
+struct VarFinder {
+    ids: Vec<Ident>,
+}
+
+impl Visit for VarFinder {
+    noop_visit_type!();
+
+    fn visit_var_decl(&mut self, decl: &VarDecl) {
+        if decl.kind == VarDeclKind::Var {
+            self.ids.extend(find_ids::<_, Ident>(&decl.decls));
+        }
+        decl.visit_children_with(self);
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+
+    fn visit_class(&mut self, _: &Class) {}
+
+    fn visit_getter_prop(&mut self, _: &GetterProp) {}
+
+    fn visit_setter_prop(&mut self, _: &SetterProp) {}
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use swc_common::{chain, SyntaxContext, DUMMY_SP};
+use swc_ecmascript::ast::*;
+use swc_ecmascript::visit::{noop_fold_type, Fold, FoldWith};
+
+use crate::const_fold::{const_fold, to_expr, Const};
+
+/// Values of the globals replaced by [define], keyed by their dotted path,
+/// like `process.env.NODE_ENV` or `__DEV__`.
+///
+/// A key ending with `*`, like `process.env.NEXT_PUBLIC_*`, replaces every
+/// path which is the part before the `*` followed by one property name, and
+/// which has no key of its own. Its value is an object of the values by that
+/// name, and the names which are not in it are replaced with `undefined`, like
+/// variables which are not set. Any other value is used for every matching
+/// path.
+pub type Config = HashMap<String, serde_json::Value>;
+
+/// Replaces global identifiers and member expressions like
+/// `process.env.NEXT_PUBLIC_API_URL` with the values in `config`, and then
+/// removes the branches made unreachable by the replaced values.
+///
+/// This does what `DefinePlugin` of webpack does, but before the other passes,
+/// so `next_ssg` and `shake_exports` don't see the code of dead branches.
+pub fn define(config: &Config) -> impl Fold + '_ {
+    chain!(Define { config }, const_fold())
+}
+
+struct Define<'a> {
+    config: &'a Config,
+}
+
+impl Define<'_> {
+    /// Returns the expression replacing `e`, if it's the path of a key.
+    fn replacement(&self, e: &Expr) -> Option<Expr> {
+        let mut path = String::new();
+        if !dotted_path(e, &mut path) {
+            return None;
+        }
+
+        if let Some(value) = self.config.get(&path) {
+            return Some(value_to_expr(value));
+        }
+
+        // The longest matching prefix wins. The `*` only stands for one property,
+        // so `process.env.NEXT_PUBLIC_FOO.length` is left to `fold_children`,
+        // which replaces `process.env.NEXT_PUBLIC_FOO`.
+        let (name, value) = self
+            .config
+            .iter()
+            .filter_map(|(key, value)| {
+                let name = path.strip_prefix(key.strip_suffix('*')?)?;
+                if name.is_empty() || name.contains('.') {
+                    return None;
+                }
+                Some((name, value))
+            })
+            .max_by_key(|(name, _)| path.len() - name.len())?;
+
+        match value {
+            serde_json::Value::Object(values) => Some(match values.get(name) {
+                Some(value) => value_to_expr(value),
+                None => to_expr(Const::Undefined),
+            }),
+            _ => Some(value_to_expr(value)),
+        }
+    }
+}
+
+/// Appends the path of `e` to `path`, if `e` is a global identifier or a chain
+/// of non-computed member expressions on one. `process.env["FOO"]` is handled
+/// like `process.env.FOO`.
+fn dotted_path(e: &Expr, path: &mut String) -> bool {
+    match e {
+        Expr::Ident(i) if i.span.ctxt == SyntaxContext::empty() => {
+            path.push_str(&i.sym);
+            true
+        }
+        Expr::Member(MemberExpr { obj, prop, .. }) => {
+            let prop = match prop {
+                MemberProp::Ident(i) => &*i.sym,
+                MemberProp::Computed(ComputedPropName { expr, .. }) => match &**expr {
+                    Expr::Lit(Lit::Str(s)) => &*s.value,
+                    _ => return false,
+                },
+                MemberProp::PrivateName(..) => return false,
+            };
+
+            if !dotted_path(obj, path) {
+                return false;
+            }
+            path.push('.');
+            path.push_str(prop);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Like [json_to_expr], but wraps the values which could be parsed differently
+/// depending on where they are inserted, like objects at the start of a
+/// statement or negative numbers after `-`.
+fn value_to_expr(value: &serde_json::Value) -> Expr {
+    let expr = json_to_expr(value);
+    let needs_paren = match value {
+        serde_json::Value::Number(n) => n.as_f64().map_or(false, |n| n.is_sign_negative()),
+        serde_json::Value::Array(..) | serde_json::Value::Object(..) => true,
+        _ => false,
+    };
+    if !needs_paren {
+        return expr;
+    }
+
+    Expr::Paren(ParenExpr {
+        span: DUMMY_SP,
+        expr: Box::new(expr),
+    })
+}
+
+fn json_to_expr(value: &serde_json::Value) -> Expr {
+    match value {
+        serde_json::Value::Null => Expr::Lit(Lit::Null(Null { span: DUMMY_SP })),
+        serde_json::Value::Bool(value) => Expr::Lit(Lit::Bool(Bool {
+            span: DUMMY_SP,
+            value: *value,
+        })),
+        serde_json::Value::Number(n) => Expr::Lit(Lit::Num(Number {
+            span: DUMMY_SP,
+            value: n.as_f64().unwrap_or(f64::NAN),
+        })),
+        serde_json::Value::String(s) => Expr::Lit(Lit::Str(str_lit(s))),
+        serde_json::Value::Array(values) => Expr::Array(ArrayLit {
+            span: DUMMY_SP,
+            elems: values
+                .iter()
+                .map(|value| {
+                    Some(ExprOrSpread {
+                        spread: None,
+                        expr: Box::new(json_to_expr(value)),
+                    })
+                })
+                .collect(),
+        }),
+        serde_json::Value::Object(props) => Expr::Object(ObjectLit {
+            span: DUMMY_SP,
+            props: props
+                .iter()
+                .map(|(key, value)| {
+                    PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                        key: PropName::Str(str_lit(key)),
+                        value: Box::new(json_to_expr(value)),
+                    })))
+                })
+                .collect(),
+        }),
+    }
+}
+
+fn str_lit(value: &str) -> Str {
+    Str {
+        span: DUMMY_SP,
+        value: value.into(),
+        has_escape: false,
+        kind: Default::default(),
+    }
+}
+
+impl Fold for Define<'_> {
+    noop_fold_type!();
+
+    fn fold_expr(&mut self, e: Expr) -> Expr {
+        if let Some(replacement) = self.replacement(&e) {
+            return replacement;
+        }
+
+        e.fold_children_with(self)
+    }
+
+    fn fold_assign_expr(&mut self, e: AssignExpr) -> AssignExpr {
+        // `process.env.FOO = 'bar'` is left as is.
+        AssignExpr {
+            right: e.right.fold_with(self),
+            ..e
+        }
+    }
+
+    fn fold_update_expr(&mut self, e: UpdateExpr) -> UpdateExpr {
+        e
+    }
+
+    fn fold_unary_expr(&mut self, e: UnaryExpr) -> UnaryExpr {
+        if e.op == UnaryOp::Delete {
+            return e;
+        }
+
+        e.fold_children_with(self)
+    }
+
+    fn fold_opt_chain_expr(&mut self, e: OptChainExpr) -> OptChainExpr {
+        // The expression of `process?.env.FOO` is `process.env.FOO`, which must not
+        // be replaced as a whole.
+        OptChainExpr {
+            expr: Box::new((*e.expr).fold_children_with(self)),
+            ..e
+        }
+    }
+
+    fn fold_prop(&mut self, p: Prop) -> Prop {
+        match p {
+            Prop::Shorthand(i) => match self.replacement(&Expr::Ident(i.clone())) {
+                Some(replacement) => Prop::KeyValue(KeyValueProp {
+                    key: PropName::Ident(i),
+                    value: Box::new(replacement),
+                }),
+                None => Prop::Shorthand(i),
+            },
+            _ => p.fold_children_with(self),
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use anyhow::{anyhow, Error};
+use serde::Serialize;
+use std::io::{self, Write};
+use std::mem::take;
+use std::sync::{Arc, Mutex};
+use swc_common::errors::{
+    ColorConfig, DiagnosticBuilder, DiagnosticId, Emitter, EmitterWriter, Handler, Level, HANDLER,
+};
+use swc_common::{BytePos, SourceMap, Span};
+
+/// `export * from '...'` in a page.
+pub const EXPORT_ALL_IN_PAGE: &str = "export-all-in-page";
+/// A named export of a page which Next.js does not use.
+pub const UNKNOWN_PAGE_EXPORT: &str = "unknown-page-export";
+/// A page without a default export, or whose default export cannot be a
+/// React component.
+pub const PAGE_WITHOUT_VALID_COMPONENT: &str = "page-without-valid-component";
+/// `getStaticPaths` exported by a page without `getStaticProps`.
+pub const GET_STATIC_PATHS_WITHOUT_GET_STATIC_PROPS: &str = "getstaticpaths-without-getstaticprops";
+/// `getInitialProps` used together with `getServerSideProps`.
+pub const GET_INITIAL_PROPS_WITH_SERVER_SIDE_PROPS: &str =
+    "get-initial-props-with-server-side-props";
+/// A React component exported by an API route.
+pub const COMPONENT_IN_API_ROUTE: &str = "component-in-api-route";
+/// Invalid `export const config` in a page.
+pub const INVALID_PAGE_CONFIG: &str = "invalid-page-config";
+/// `next/dynamic` called with options that are not an object literal.
+pub const INVALID_DYNAMIC_OPTIONS_TYPE: &str = "invalid-dynamic-options-type";
+/// `next/dynamic` called with a wrong number of arguments.
+pub const INVALID_DYNAMIC_ARGUMENTS: &str = "invalid-dynamic-arguments";
+/// `getStaticProps` or `getStaticPaths` used together with
+/// `getServerSideProps`.
+pub const SSG_WITH_SERVER_SIDE_PROPS: &str = "ssg-with-server-side-props";
+/// An import used by a data function which is kept in the client bundle,
+/// because other code uses it too.
+pub const SSG_IMPORT_RETAINED: &str = "ssg-import-retained";
+/// A `graphql` template which cannot be replaced with a Relay artifact.
+pub const RELAY_ARTIFACT: &str = "relay-artifact";
+/// A Wasm plugin which could not be run or returned an error.
+pub const PLUGIN_FAILED: &str = "plugin-failed";
+/// `import` / `export` used together with `module.exports` or `exports`.
+pub const MIXED_MODULE_SYNTAX: &str = "mixed-module-syntax";
+/// A default or namespace import of a package configured with
+/// `preventFullImport`.
+pub const FULL_PACKAGE_IMPORT: &str = "full-package-import";
+/// A `modularizeImports` path template which cannot be rendered.
+pub const INVALID_IMPORT_TEMPLATE: &str = "invalid-import-template";
+/// A `"use client"` or `"use server"` directive which is not at the top of the
+/// file, or both directives in one file.
+pub const MISPLACED_DIRECTIVE: &str = "misplaced-directive";
+/// `export * from '...'` in a `"use client"` module.
+pub const EXPORT_ALL_IN_CLIENT_MODULE: &str = "export-all-in-client-module";
+/// `server-only` imported by a module compiled for the client.
+pub const SERVER_ONLY_IMPORT: &str = "server-only-import";
+/// `client-only` imported by a module compiled for the server.
+pub const CLIENT_ONLY_IMPORT: &str = "client-only-import";
+/// A Node.js built-in module imported by a module compiled for the client.
+pub const NODE_BUILTIN_IMPORT: &str = "node-builtin-import";
+/// A Node.js API used by a page with `runtime: 'edge'`.
+pub const EDGE_RUNTIME_UNSUPPORTED: &str = "edge-runtime-unsupported";
+
+/// Returns the page of the Next.js documentation explaining `code`.
+pub fn docs_url(code: &str) -> Option<String> {
+    match code {
+        EXPORT_ALL_IN_PAGE
+        | UNKNOWN_PAGE_EXPORT
+        | PAGE_WITHOUT_VALID_COMPONENT
+        | GET_STATIC_PATHS_WITHOUT_GET_STATIC_PROPS
+        | GET_INITIAL_PROPS_WITH_SERVER_SIDE_PROPS
+        | COMPONENT_IN_API_ROUTE
+        | INVALID_PAGE_CONFIG
+        | INVALID_DYNAMIC_OPTIONS_TYPE => {
+            Some(format!("https://nextjs.org/docs/messages/{}", code))
+        }
+        _ => None,
+    }
+}
+
+/// Emits an error with a stable `code` using [HANDLER].
+pub(crate) fn emit_error(span: Span, code: &str, message: &str) {
+    HANDLER.with(|handler| {
+        handler
+            .struct_span_err_with_code(span, message, DiagnosticId::Error(code.into()))
+            .emit()
+    });
+}
+
+/// Emits a warning with a stable `code` using `handler`.
+pub(crate) fn emit_warning(handler: &Handler, span: Span, code: &str, message: &str) {
+    handler
+        .struct_span_warn_with_code(span, message, DiagnosticId::Lint(code.into()))
+        .emit();
+}
+
+/// Edits which fix a diagnostic, before their spans are resolved.
+pub(crate) struct Suggestion {
+    pub message: String,
+    pub edits: Vec<(Span, String)>,
+}
+
+/// Like [emit_error], but attaches `suggestion` to the diagnostic. It's
+/// returned as a [Fix] by [try_with_diagnostics].
+pub(crate) fn emit_error_with_fix(span: Span, code: &str, message: &str, suggestion: Suggestion) {
+    HANDLER.with(|handler| {
+        handler
+            .struct_span_err_with_code(span, message, DiagnosticId::Error(code.into()))
+            .multipart_suggestion(&suggestion.message, suggestion.edits)
+            .emit()
+    });
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Position {
+    /// 1-based line number.
+    pub line: usize,
+    /// 0-based column, counted in characters.
+    pub column: usize,
+}
+
+impl Position {
+    fn new(cm: &SourceMap, pos: BytePos) -> Self {
+        let loc = cm.lookup_char_pos(pos);
+
+        Position {
+            line: loc.line,
+            column: loc.col.0,
+        }
+    }
+}
+
+/// Replaces the source between `start` and `end` with `replacement`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Edit {
+    pub start: Position,
+    pub end: Position,
+    pub replacement: String,
+}
+
+/// Machine-applicable fix for a diagnostic.
+#[derive(Clone, Debug, Serialize)]
+pub struct Fix {
+    pub message: String,
+    pub edits: Vec<Edit>,
+}
+
+/// A diagnostic which can be consumed without parsing the formatted message.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub code: Option<String>,
+    pub severity: Severity,
+    pub file: Option<String>,
+    pub start: Option<Position>,
+    pub end: Option<Position>,
+    pub message: String,
+    pub docs_url: Option<String>,
+    pub fixes: Vec<Fix>,
+}
+
+impl Diagnostic {
+    fn new(cm: &SourceMap, db: &DiagnosticBuilder) -> Self {
+        let code = match &db.code {
+            Some(DiagnosticId::Error(code)) | Some(DiagnosticId::Lint(code)) => Some(code.clone()),
+            None => None,
+        };
+        let severity = match db.level {
+            Level::Bug | Level::Fatal | Level::PhaseFatal | Level::Error => Severity::Error,
+            Level::Warning => Severity::Warning,
+            _ => Severity::Info,
+        };
+
+        let (file, start, end) = match db.span.primary_span() {
+            Some(span) if !span.is_dummy() => (
+                Some(cm.span_to_filename(span).to_string()),
+                Some(Position::new(cm, span.lo)),
+                Some(Position::new(cm, span.hi)),
+            ),
+            _ => (None, None, None),
+        };
+        let fixes = db
+            .suggestions
+            .iter()
+            .flat_map(|suggestion| {
+                suggestion
+                    .substitutions
+                    .iter()
+                    .map(move |substitution| Fix {
+                        message: suggestion.msg.clone(),
+                        edits: substitution
+                            .parts
+                            .iter()
+                            .map(|part| Edit {
+                                start: Position::new(cm, part.span.lo),
+                                end: Position::new(cm, part.span.hi),
+                                replacement: part.snippet.clone(),
+                            })
+                            .collect(),
+                    })
+            })
+            .collect();
+
+        Diagnostic {
+            docs_url: code.as_deref().and_then(docs_url),
+            code,
+            severity,
+            file,
+            start,
+            end,
+            message: db.message(),
+            fixes,
+        }
+    }
+
+    /// An error which is not reported at a location, like a file which cannot
+    /// be read.
+    pub fn error(message: String) -> Self {
+        Diagnostic {
+            code: None,
+            severity: Severity::Error,
+            file: None,
+            start: None,
+            end: None,
+            message,
+            docs_url: None,
+            fixes: vec![],
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+#[derive(Clone, Default)]
+struct LockedWriter(Arc<Mutex<Vec<u8>>>);
+
+impl Write for LockedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Records every diagnostic before handing it to the formatting emitter.
+struct DiagnosticCollector {
+    cm: Arc<SourceMap>,
+    inner: EmitterWriter,
+    diagnostics: Arc<Mutex<Vec<Diagnostic>>>,
+}
+
+impl Emitter for DiagnosticCollector {
+    fn emit(&mut self, db: &DiagnosticBuilder<'_>) {
+        self.diagnostics
+            .lock()
+            .unwrap()
+            .push(Diagnostic::new(&self.cm, db));
+
+        if db.suggestions.is_empty() {
+            self.inner.emit(db);
+            return;
+        }
+
+        // The fixes are only returned as [Fix]es, so the formatted message stays
+        // the same.
+        let mut diagnostic = (**db).clone();
+        diagnostic.suggestions.clear();
+        let handler = Handler::with_tty_emitter(ColorConfig::Never, false, false, None);
+        let mut db = DiagnosticBuilder::new_diagnostic(&handler, diagnostic);
+        self.inner.emit(&db);
+        db.cancel();
+    }
+}
+
+/// Like `swc::try_with_handler`, but also returns the emitted diagnostics in a
+/// structured form.
+pub fn try_with_diagnostics<F, Ret>(
+    cm: Arc<SourceMap>,
+    skip_filename: bool,
+    op: F,
+) -> (Result<Ret, Error>, Vec<Diagnostic>)
+where
+    F: FnOnce(&Handler) -> Result<Ret, Error>,
+{
+    let wr = LockedWriter::default();
+    let diagnostics: Arc<Mutex<Vec<Diagnostic>>> = Default::default();
+    let emitter = DiagnosticCollector {
+        cm: cm.clone(),
+        inner: EmitterWriter::new(Box::new(wr.clone()), Some(cm), false, true)
+            .skip_filename(skip_filename),
+        diagnostics: diagnostics.clone(),
+    };
+    let handler = Handler::with_emitter(true, false, Box::new(emitter));
+
+    let ret = HANDLER.set(&handler, || op(&handler));
+    let ret = if handler.has_errors() {
+        let msg = String::from_utf8(take(&mut *wr.0.lock().unwrap()))
+            .expect("error string should be utf8");
+
+        match ret {
+            Ok(_) => Err(anyhow!(msg)),
+            Err(err) => Err(err.context(msg)),
+        }
+    } else {
+        ret
+    };
+
+    let diagnostics = take(&mut *diagnostics.lock().unwrap());
+    (ret, diagnostics)
+}
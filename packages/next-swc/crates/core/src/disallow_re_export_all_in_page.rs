@@ -1,8 +1,9 @@
 use swc_common::pass::Optional;
 use swc_ecmascript::ast::ExportAll;
-use swc_ecmascript::utils::HANDLER;
 use swc_ecmascript::visit::{noop_fold_type, Fold};
 
+use crate::diagnostics::{emit_error_with_fix, Suggestion, EXPORT_ALL_IN_PAGE};
+
 pub fn disallow_re_export_all_in_page(is_page_file: bool) -> impl Fold {
     Optional::new(DisallowReExportAllInPage, is_page_file)
 }
@@ -13,14 +14,38 @@ impl Fold for DisallowReExportAllInPage {
     noop_fold_type!();
 
     fn fold_export_all(&mut self, e: ExportAll) -> ExportAll {
-        HANDLER.with(|handler| {
-          handler
-            .struct_span_err(
-              e.span,
-              "Using `export * from '...'` in a page is disallowed. Please use `export { default } from '...'` instead.\nRead more: https://nextjs.org/docs/messages/export-all-in-page",
-            )
-            .emit()
-        });
+        emit_error_with_fix(
+            e.span,
+            EXPORT_ALL_IN_PAGE,
+            "Using `export * from '...'` in a page is disallowed. Please use `export { default } \
+             from '...'` instead.\nRead more: https://nextjs.org/docs/messages/export-all-in-page",
+            Suggestion {
+                message: "Re-export only the default export".into(),
+                edits: vec![(
+                    e.span,
+                    format!("export {{ default }} from {}", quote(&e.src.value)),
+                )],
+            },
+        );
         e
     }
 }
+
+/// Returns `value` as a single-quoted JavaScript string literal.
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('\'');
+    for c in value.chars() {
+        match c {
+            '\'' => quoted.push_str("\\'"),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\u{2028}' | '\u{2029}' => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
@@ -0,0 +1,154 @@
+use swc_common::{Span, SyntaxContext};
+use swc_ecmascript::ast::*;
+use swc_ecmascript::visit::{noop_fold_type, noop_visit_type, Fold, Visit, VisitWith};
+
+use crate::diagnostics::{emit_error, EDGE_RUNTIME_UNSUPPORTED};
+use crate::environment_imports::is_node_builtin;
+use crate::metadata::{Runtime, SharedMetadata};
+
+/// Reports the Node.js APIs used by a page which runs in the Edge Runtime.
+///
+/// The runtime is read from the config found by `page_config`, so this pass
+/// runs after it.
+pub fn edge_runtime(is_page_file: bool, metadata: SharedMetadata) -> impl Fold {
+    EdgeRuntime {
+        is_page_file,
+        metadata,
+    }
+}
+
+struct EdgeRuntime {
+    is_page_file: bool,
+    metadata: SharedMetadata,
+}
+
+impl Fold for EdgeRuntime {
+    noop_fold_type!();
+
+    fn fold_module(&mut self, module: Module) -> Module {
+        let is_edge_runtime = self
+            .metadata
+            .borrow()
+            .page_config
+            .as_ref()
+            .and_then(|config| config.runtime)
+            .map_or(false, Runtime::is_edge);
+        if self.is_page_file && is_edge_runtime {
+            module.visit_with(&mut EdgeRuntimeChecker);
+        }
+
+        module
+    }
+
+    fn fold_script(&mut self, script: Script) -> Script {
+        script
+    }
+}
+
+struct EdgeRuntimeChecker;
+
+fn is_global(e: &Expr, sym: &str) -> bool {
+    matches!(e, Expr::Ident(i) if &*i.sym == sym && i.span.ctxt == SyntaxContext::empty())
+}
+
+fn report(span: Span, api: &str) {
+    emit_error(
+        span,
+        EDGE_RUNTIME_UNSUPPORTED,
+        &format!(
+            "{} is not supported in the Edge Runtime. See: \
+             https://nextjs.org/docs/api-reference/edge-runtime",
+            api
+        ),
+    );
+}
+
+fn check_import(src: &Str) {
+    if is_node_builtin(&src.value) {
+        report(src.span, &format!("The Node.js module `{}`", src.value));
+    }
+}
+
+impl Visit for EdgeRuntimeChecker {
+    noop_visit_type!();
+
+    fn visit_import_decl(&mut self, decl: &ImportDecl) {
+        if !decl.type_only {
+            check_import(&decl.src);
+        }
+    }
+
+    fn visit_named_export(&mut self, export: &NamedExport) {
+        if let Some(src) = &export.src {
+            if !export.type_only {
+                check_import(src);
+            }
+        }
+    }
+
+    fn visit_export_all(&mut self, export: &ExportAll) {
+        check_import(&export.src);
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        match &call.callee {
+            Callee::Import(..) => {
+                if let Some(ExprOrSpread { spread: None, expr }) = call.args.first() {
+                    if let Expr::Lit(Lit::Str(src)) = &**expr {
+                        check_import(src);
+                    }
+                }
+            }
+            Callee::Expr(callee) if is_global(callee, "require") => {
+                report(call.span, "`require`");
+            }
+            Callee::Expr(callee) if is_global(callee, "eval") => {
+                report(call.span, "`eval`");
+            }
+            Callee::Expr(callee) if is_global(callee, "Function") => {
+                report(call.span, "`Function`");
+            }
+            _ => {}
+        }
+
+        call.visit_children_with(self);
+    }
+
+    fn visit_new_expr(&mut self, new: &NewExpr) {
+        if is_global(&new.callee, "Function") {
+            report(new.span, "`new Function`");
+        }
+
+        new.visit_children_with(self);
+    }
+
+    fn visit_member_expr(&mut self, e: &MemberExpr) {
+        if is_global(&e.obj, "process") {
+            if let MemberProp::Ident(prop) = &e.prop {
+                if &*prop.sym == "cwd" {
+                    report(e.span, "`process.cwd`");
+                }
+            }
+        }
+
+        // Only the object and computed properties can be references.
+        e.obj.visit_with(self);
+        if let MemberProp::Computed(prop) = &e.prop {
+            prop.visit_with(self);
+        }
+    }
+
+    fn visit_prop_name(&mut self, name: &PropName) {
+        if let PropName::Computed(name) = name {
+            name.visit_with(self);
+        }
+    }
+
+    fn visit_ident(&mut self, i: &Ident) {
+        if (&*i.sym == "__dirname" || &*i.sym == "__filename")
+            && i.span.ctxt == SyntaxContext::empty()
+        {
+            report(i.span, &format!("`{}`", i.sym));
+        }
+    }
+}
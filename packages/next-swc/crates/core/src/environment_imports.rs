@@ -0,0 +1,162 @@
+use serde::Deserialize;
+use swc_common::{Span, SyntaxContext};
+use swc_ecma_loader::NODE_BUILTINS;
+use swc_ecmascript::ast::*;
+use swc_ecmascript::visit::{noop_visit_type, Fold, Visit, VisitWith};
+
+use crate::diagnostics::{emit_error, CLIENT_ONLY_IMPORT, NODE_BUILTIN_IMPORT, SERVER_ONLY_IMPORT};
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    /// Also report imports of Node.js built-in modules in the client build.
+    ///
+    /// The modules which webpack replaces with a browser fallback, like `path`
+    /// and `buffer`, are allowed.
+    #[serde(default = "default_node_builtins")]
+    pub node_builtins: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            node_builtins: default_node_builtins(),
+        }
+    }
+}
+
+fn default_node_builtins() -> bool {
+    true
+}
+
+/// Node.js modules with a browser fallback in the webpack config of Next.js.
+const BROWSER_FALLBACKS: &[&str] = &[
+    "assert",
+    "buffer",
+    "constants",
+    "crypto",
+    "domain",
+    "events",
+    "http",
+    "https",
+    "os",
+    "path",
+    "process",
+    "punycode",
+    "querystring",
+    "stream",
+    "string_decoder",
+    "sys",
+    "timers",
+    "tty",
+    "util",
+    "vm",
+    "zlib",
+];
+
+/// Reports imports of modules which cannot be used in the current build:
+/// `server-only` in the client build and `client-only` in the server build.
+pub fn environment_imports(config: Config, is_server: bool) -> impl Fold {
+    EnvironmentImports { config, is_server }
+}
+
+struct EnvironmentImports {
+    config: Config,
+    is_server: bool,
+}
+
+pub(crate) fn is_node_builtin(src: &str) -> bool {
+    src.starts_with("node:") || NODE_BUILTINS.contains(&src)
+}
+
+impl EnvironmentImports {
+    fn check(&self, src: &str, span: Span) {
+        if self.is_server {
+            if src == "client-only" {
+                emit_error(
+                    span,
+                    CLIENT_ONLY_IMPORT,
+                    "`client-only` cannot be imported from a module which is compiled for the \
+                     server.",
+                );
+            }
+            return;
+        }
+
+        if src == "server-only" {
+            emit_error(
+                span,
+                SERVER_ONLY_IMPORT,
+                "`server-only` cannot be imported from a module which is compiled for the client.",
+            );
+        } else if self.config.node_builtins
+            && is_node_builtin(src)
+            && !BROWSER_FALLBACKS.contains(&src.trim_start_matches("node:"))
+        {
+            emit_error(
+                span,
+                NODE_BUILTIN_IMPORT,
+                &format!(
+                    "The Node.js module `{}` cannot be imported from a module which is compiled \
+                     for the client.",
+                    src
+                ),
+            );
+        }
+    }
+}
+
+impl Fold for EnvironmentImports {
+    fn fold_module(&mut self, module: Module) -> Module {
+        module.visit_with(self);
+        module
+    }
+
+    fn fold_script(&mut self, script: Script) -> Script {
+        script.visit_with(self);
+        script
+    }
+}
+
+impl Visit for EnvironmentImports {
+    noop_visit_type!();
+
+    fn visit_import_decl(&mut self, decl: &ImportDecl) {
+        if !decl.type_only {
+            self.check(&decl.src.value, decl.src.span);
+        }
+    }
+
+    fn visit_named_export(&mut self, export: &NamedExport) {
+        if let Some(src) = &export.src {
+            if !export.type_only {
+                self.check(&src.value, src.span);
+            }
+        }
+    }
+
+    fn visit_export_all(&mut self, export: &ExportAll) {
+        self.check(&export.src.value, export.src.span);
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        let is_import = match &call.callee {
+            Callee::Import(..) => true,
+            Callee::Expr(callee) => matches!(
+                &**callee,
+                Expr::Ident(i) if &*i.sym == "require" && i.span.ctxt == SyntaxContext::empty()
+            ),
+            Callee::Super(..) => false,
+        };
+
+        if is_import {
+            if let Some(ExprOrSpread { spread: None, expr }) = call.args.first() {
+                if let Expr::Lit(Lit::Str(src)) = &**expr {
+                    self.check(&src.value, src.span);
+                }
+            }
+        }
+
+        call.visit_children_with(self);
+    }
+}
@@ -29,36 +29,49 @@ DEALINGS IN THE SOFTWARE.
 #![recursion_limit = "2048"]
 #![deny(clippy::all)]
 
-use auto_cjs::contains_cjs;
-use either::Either;
+use auto_cjs::{module_kind, ModuleKind};
+use diagnostics::{emit_warning, MIXED_MODULE_SYNTAX};
+use metadata::SharedMetadata;
+use pipeline::{PassName, Passes, Pipeline};
 use serde::Deserialize;
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::{path::PathBuf, sync::Arc};
-use swc::config::ModuleConfig;
-use swc_common::{self, chain, pass::Optional};
+use swc::{config::ModuleConfig, Compiler};
+use swc_common::{self, chain, errors::Handler};
 use swc_common::{SourceFile, SourceMap};
-use swc_ecmascript::ast::EsVersion;
-use swc_ecmascript::transforms::pass::noop;
-use swc_ecmascript::{
-    parser::{lexer::Lexer, Parser, StringInput},
-    visit::Fold,
-};
+use swc_ecmascript::ast::{EsVersion, Program};
+use swc_ecmascript::visit::Fold;
 
 pub mod amp_attributes;
 mod auto_cjs;
+mod const_fold;
+pub mod define;
+pub mod diagnostics;
 pub mod disallow_re_export_all_in_page;
+pub mod edge_runtime;
+pub mod environment_imports;
 pub mod hook_optimizer;
+pub mod metadata;
+pub mod modularize_imports;
 pub mod next_dynamic;
 pub mod next_ssg;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod optimize_barrels;
 pub mod page_config;
+pub mod page_exports;
+pub mod pipeline;
+pub mod plugin;
 pub mod react_remove_properties;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod relay;
 pub mod remove_console;
+pub mod server_components;
 pub mod shake_exports;
 pub mod styled_jsx;
 mod top_level_binding_collector;
+pub mod trace;
+pub mod typeof_window;
 
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -66,9 +79,11 @@ pub struct TransformOptions {
     #[serde(flatten)]
     pub swc: swc::config::Options,
 
+    /// Same as disabling `next_ssg` in [TransformOptions::pipeline].
     #[serde(default)]
     pub disable_next_ssg: bool,
 
+    /// Same as disabling `page_config` in [TransformOptions::pipeline].
     #[serde(default)]
     pub disable_page_config: bool,
 
@@ -99,94 +114,280 @@ pub struct TransformOptions {
 
     #[serde(default)]
     pub shake_exports: Option<shake_exports::Config>,
+
+    /// In development, warn about the imports which `next_ssg` keeps because
+    /// client code uses them. They are listed in the metadata either way.
+    #[serde(default)]
+    pub warn_ssg_retained_imports: bool,
+
+    /// Globals like `process.env.NODE_ENV` to replace with a value, inlined by
+    /// the `define` pass.
+    #[serde(default)]
+    pub define: define::Config,
+
+    /// Packages whose named imports are rewritten to imports of their
+    /// members, like `lodash`.
+    #[serde(default)]
+    pub modularize_imports: Option<modularize_imports::Config>,
+
+    /// Handle `"use client"` and `"use server"` directives.
+    #[serde(default)]
+    pub server_components: bool,
+
+    /// Options of the check for imports of `server-only`, `client-only` and
+    /// Node.js built-in modules.
+    #[serde(default)]
+    pub environment_imports: environment_imports::Config,
+
+    /// Import the members of barrel files from the modules which define them.
+    #[serde(default)]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub optimize_barrels: Option<optimize_barrels::Config>,
+
+    /// Remove the branches of `typeof window` checks which are dead for
+    /// `is_server`.
+    #[serde(default)]
+    pub typeof_window: Option<typeof_window::Config>,
+
+    /// Return the diagnostics of a failed transform instead of an error
+    /// message.
+    #[serde(default)]
+    pub json_diagnostics: bool,
+
+    /// Wasm plugins, run in order by the `plugins` pass. Builds without the
+    /// `plugin` feature report them as errors.
+    #[serde(default)]
+    pub plugins: Vec<plugin::PluginConfig>,
+
+    /// Suffix of the marker of dropped AMP-only client files. Defaults to the
+    /// `SOURCE_DATE_EPOCH` environment variable, or a digest of the source.
+    #[serde(default)]
+    pub source_date_epoch: Option<String>,
+
+    /// Write timing spans of the transform to a trace file.
+    #[serde(default)]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub trace: Option<trace::TraceConfig>,
+
+    /// Passes of [custom_before_pass] to run, in order.
+    #[serde(default)]
+    pub pipeline: Pipeline,
 }
 
 pub fn custom_before_pass(
     cm: Arc<SourceMap>,
     file: Arc<SourceFile>,
     opts: &TransformOptions,
+    metadata: SharedMetadata,
 ) -> impl Fold + '_ {
-    #[cfg(target_arch = "wasm32")]
-    let relay_plugin = noop();
+    chain!(
+        shared_before_pass(cm.clone(), file.clone(), opts, metadata.clone()),
+        target_before_pass(cm, file, opts, metadata)
+    )
+}
 
-    #[cfg(not(target_arch = "wasm32"))]
-    let relay_plugin = {
-        if let Some(config) = &opts.relay {
-            Either::Left(relay::relay(
-                config,
+/// The passes of [custom_before_pass] which do not depend on the target, so
+/// their output can be shared by the server and client builds of a file.
+pub fn shared_before_pass(
+    cm: Arc<SourceMap>,
+    file: Arc<SourceFile>,
+    opts: &TransformOptions,
+    metadata: SharedMetadata,
+) -> impl Fold + '_ {
+    Passes(
+        opts.passes()
+            .filter(|pass| pass.is_shared())
+            .filter_map(|pass| Some((pass, before_pass(pass, &cm, &file, opts, &metadata)?)))
+            .collect(),
+    )
+}
+
+/// The passes of [custom_before_pass] which run after [shared_before_pass] and
+/// must be applied once per target.
+pub fn target_before_pass(
+    cm: Arc<SourceMap>,
+    file: Arc<SourceFile>,
+    opts: &TransformOptions,
+    metadata: SharedMetadata,
+) -> impl Fold + '_ {
+    Passes(
+        opts.passes()
+            .filter(|pass| !pass.is_shared())
+            .filter_map(|pass| Some((pass, before_pass(pass, &cm, &file, opts, &metadata)?)))
+            .collect(),
+    )
+}
+
+/// Creates `pass`, or returns `None` if it's disabled by `opts`.
+fn before_pass<'a>(
+    pass: PassName,
+    cm: &Arc<SourceMap>,
+    file: &Arc<SourceFile>,
+    opts: &'a TransformOptions,
+    metadata: &SharedMetadata,
+) -> Option<Box<dyn Fold + 'a>> {
+    let pass: Box<dyn Fold + 'a> = match pass {
+        PassName::DisallowReExportAllInPage => Box::new(
+            disallow_re_export_all_in_page::disallow_re_export_all_in_page(opts.is_page_file),
+        ),
+        PassName::PageExports => Box::new(page_exports::page_exports(
+            &file.name,
+            opts.pages_dir.as_deref(),
+            opts.is_page_file,
+        )),
+        PassName::StyledJsx => Box::new(styled_jsx::styled_jsx(
+            cm.clone(),
+            file.name.clone(),
+            metadata.clone(),
+        )),
+        PassName::HookOptimizer => Box::new(hook_optimizer::hook_optimizer()),
+        PassName::ModularizeImports => Box::new(modularize_imports::modularize_imports(
+            opts.modularize_imports.as_ref()?,
+        )),
+        PassName::StyledComponents => {
+            let config = Rc::new(opts.styled_components.clone()?);
+            let state: Rc<RefCell<styled_components::State>> = Default::default();
+
+            Box::new(chain!(
+                styled_components::analyzer(config.clone(), state.clone()),
+                styled_components::display_name_and_id(file.clone(), config, state)
+            ))
+        }
+        PassName::Define if !opts.define.is_empty() => Box::new(define::define(&opts.define)),
+        PassName::TypeofWindow => Box::new(typeof_window::typeof_window(
+            opts.typeof_window.clone()?,
+            opts.is_server,
+        )),
+        #[cfg(not(target_arch = "wasm32"))]
+        PassName::OptimizeBarrels => Box::new(optimize_barrels::optimize_barrels(
+            opts.optimize_barrels.as_ref()?,
+            file.name.clone(),
+            opts.is_server,
+            metadata.clone(),
+        )),
+        PassName::ServerComponents if opts.server_components => {
+            Box::new(server_components::server_components(
                 file.name.clone(),
-                opts.pages_dir.clone(),
+                opts.is_server,
+                metadata.clone(),
             ))
-        } else {
-            Either::Right(noop())
         }
-    };
-
-    chain!(
-        disallow_re_export_all_in_page::disallow_re_export_all_in_page(opts.is_page_file),
-        styled_jsx::styled_jsx(cm, file.name.clone()),
-        hook_optimizer::hook_optimizer(),
-        match &opts.styled_components {
-            Some(config) => {
-                let config = Rc::new(config.clone());
-                let state: Rc<RefCell<styled_components::State>> = Default::default();
-
-                Either::Left(chain!(
-                    styled_components::analyzer(config.clone(), state.clone()),
-                    styled_components::display_name_and_id(file.clone(), config, state)
-                ))
-            }
-            None => {
-                Either::Right(noop())
-            }
-        },
-        Optional::new(next_ssg::next_ssg(), !opts.disable_next_ssg),
-        amp_attributes::amp_attributes(),
-        next_dynamic::next_dynamic(
+        PassName::EnvironmentImports => Box::new(environment_imports::environment_imports(
+            opts.environment_imports.clone(),
+            opts.is_server,
+        )),
+        PassName::NextSsg => Box::new(next_ssg::next_ssg(
+            opts.is_development && opts.warn_ssg_retained_imports,
+            metadata.clone(),
+        )),
+        PassName::AmpAttributes => Box::new(amp_attributes::amp_attributes()),
+        PassName::NextDynamic => Box::new(next_dynamic::next_dynamic(
             opts.is_development,
             opts.is_server,
             file.name.clone(),
-            opts.pages_dir.clone()
-        ),
-        Optional::new(
-            page_config::page_config(opts.is_development, opts.is_page_file),
-            !opts.disable_page_config
-        ),
-        relay_plugin,
-        match &opts.remove_console {
-            Some(config) if config.truthy() =>
-                Either::Left(remove_console::remove_console(config.clone())),
-            _ => Either::Right(noop()),
+            opts.pages_dir.clone(),
+            metadata.clone(),
+        )),
+        PassName::PageConfig => Box::new(page_config::page_config(
+            opts.is_development,
+            opts.is_page_file,
+            &file.src,
+            opts.source_date_epoch.as_deref(),
+            metadata.clone(),
+        )),
+        PassName::EdgeRuntime => Box::new(edge_runtime::edge_runtime(
+            opts.is_page_file,
+            metadata.clone(),
+        )),
+        #[cfg(not(target_arch = "wasm32"))]
+        PassName::Relay => Box::new(relay::relay(
+            opts.relay.as_ref()?,
+            file.name.clone(),
+            opts.pages_dir.clone(),
+            metadata.clone(),
+        )),
+        PassName::RemoveConsole => match &opts.remove_console {
+            Some(config) if config.truthy() => {
+                Box::new(remove_console::remove_console(config.clone()))
+            }
+            _ => return None,
         },
-        match &opts.react_remove_properties {
-            Some(config) if config.truthy() =>
-                Either::Left(react_remove_properties::remove_properties(config.clone())),
-            _ => Either::Right(noop()),
+        PassName::ReactRemoveProperties => match &opts.react_remove_properties {
+            Some(config) if config.truthy() => {
+                Box::new(react_remove_properties::remove_properties(config.clone()))
+            }
+            _ => return None,
         },
-        match &opts.shake_exports {
-            Some(config) => Either::Left(shake_exports::shake_exports(config.clone())),
-            None => Either::Right(noop()),
+        PassName::ShakeExports => {
+            Box::new(shake_exports::shake_exports(opts.shake_exports.clone()?))
         }
-    )
+        PassName::Plugins if !opts.plugins.is_empty() => Box::new(plugin::plugins(
+            &opts.plugins,
+            file.name.clone(),
+            opts.is_server,
+            opts.is_development,
+        )),
+        _ => return None,
+    };
+
+    Some(pass)
 }
 
 impl TransformOptions {
-    pub fn patch(mut self, fm: &SourceFile) -> Self {
+    /// The passes of [TransformOptions::pipeline], without the ones disabled by
+    /// the older `disableNextSsg` and `disablePageConfig` options.
+    pub fn passes(&self) -> impl Iterator<Item = PassName> + '_ {
+        self.pipeline.passes().filter(move |pass| match pass {
+            PassName::NextSsg => !self.disable_next_ssg,
+            PassName::PageConfig => !self.disable_page_config,
+            _ => true,
+        })
+    }
+
+    /// Parses `fm` with the parser options of `self.swc`. The program should be
+    /// passed to [TransformOptions::patch] and then to swc, so the file is only
+    /// parsed once.
+    pub fn parse(
+        &self,
+        c: &Compiler,
+        fm: Arc<SourceFile>,
+        handler: &Handler,
+    ) -> Result<Program, anyhow::Error> {
+        c.parse_js(
+            fm,
+            handler,
+            self.swc.config.jsc.target.unwrap_or_else(EsVersion::latest),
+            self.swc.config.jsc.syntax.unwrap_or_default(),
+            self.swc.is_module,
+            true,
+        )
+    }
+
+    /// Disables `.swcrc` and, unless a module config is set, picks one based on
+    /// the module syntax used by `program`.
+    pub fn patch(mut self, program: &Program, handler: &Handler) -> Self {
         self.swc.swcrc = false;
 
-        let should_enable_commonjs =
-            self.swc.config.module.is_none() && fm.src.contains("module.exports") && {
-                let syntax = self.swc.config.jsc.syntax.unwrap_or_default();
-                let target = self.swc.config.jsc.target.unwrap_or_else(EsVersion::latest);
-                let lexer = Lexer::new(syntax, target, StringInput::from(&*fm), None);
-                let mut p = Parser::new_from(lexer);
-                p.parse_module()
-                    .map(|m| contains_cjs(&m))
-                    .unwrap_or_default()
+        if self.swc.config.module.is_none() {
+            let use_commonjs = match module_kind(program) {
+                ModuleKind::Esm => false,
+                ModuleKind::Cjs => true,
+                ModuleKind::Mixed { span } => {
+                    emit_warning(
+                        handler,
+                        span,
+                        MIXED_MODULE_SYNTAX,
+                        "CommonJS exports are used together with ES module syntax. The file is \
+                         compiled as CommonJS, so `export` declarations and `module.exports` may \
+                         overwrite each other.",
+                    );
+                    true
+                }
             };
 
-        if should_enable_commonjs {
-            self.swc.config.module = Some(ModuleConfig::CommonJs(Default::default()));
+            if use_commonjs {
+                self.swc.config.module = Some(ModuleConfig::CommonJs(Default::default()));
+            }
         }
 
         self
@@ -0,0 +1,190 @@
+use serde::Serialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::server_components::Directive;
+
+/// Handle shared between the passes of [crate::custom_before_pass] so they can
+/// report what they found while transforming a file.
+pub type SharedMetadata = Rc<RefCell<TransformMetadata>>;
+
+/// Information about a file collected by the Next.js passes.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransformMetadata {
+    pub next_ssg: SsgMetadata,
+
+    /// Calls to `next/dynamic` rewritten by `next_dynamic`.
+    pub next_dynamic: Vec<DynamicImportMetadata>,
+
+    /// Values of `export const config`, if the file has one.
+    pub page_config: Option<PageConfigMetadata>,
+
+    /// True if `styled_jsx` found a `<style jsx>` element or `css.resolve`.
+    pub styled_jsx: bool,
+
+    /// True if `relay` replaced at least one `graphql` template.
+    pub relay: bool,
+
+    /// The `"use client"` or `"use server"` directive of the file.
+    pub directive: Option<Directive>,
+
+    /// Imports of barrel files which `optimize_barrels` could not rewrite.
+    pub unoptimized_barrels: Vec<UnoptimizedBarrel>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SsgMetadata {
+    /// The file exports `getStaticProps` or `getStaticPaths`.
+    pub is_ssg: bool,
+
+    /// The file exports `getServerSideProps`.
+    pub is_ssp: bool,
+
+    /// Imports and declarations removed because only data functions used
+    /// them, in the order they were removed.
+    pub removed: Vec<RemovedBinding>,
+
+    /// Imports used by data functions which are kept, because other code
+    /// uses them too.
+    pub retained_imports: Vec<RetainedImport>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemovedBinding {
+    /// Local name of the binding.
+    pub name: String,
+
+    pub kind: BindingKind,
+
+    /// Source of the import, if the binding is an import.
+    pub source: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BindingKind {
+    Import,
+    Function,
+    Variable,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetainedImport {
+    /// Local name of the import.
+    pub name: String,
+
+    pub source: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DynamicImportMetadata {
+    /// Specifier passed to `import()` in the loader.
+    pub specifier: String,
+
+    /// False if the call was made with `{ ssr: false }`.
+    pub ssr: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnoptimizedBarrel {
+    /// Source of the import, like `@acme/ui`.
+    pub source: String,
+
+    pub reason: String,
+}
+
+/// Values of `export const config` in a page.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageConfigMetadata {
+    pub amp: Option<AmpConfig>,
+
+    /// Options of an API route.
+    pub api: Option<ApiConfig>,
+
+    pub runtime: Option<Runtime>,
+
+    /// Regions an edge page or API route is deployed to.
+    pub regions: Option<Regions>,
+
+    #[serde(rename = "unstable_runtimeJS")]
+    pub unstable_runtime_js: Option<bool>,
+
+    #[serde(rename = "unstable_JsPreload")]
+    pub unstable_js_preload: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum AmpConfig {
+    /// `amp: true` or `amp: false`
+    Bool(bool),
+    /// `amp: 'hybrid'`
+    Str(String),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiConfig {
+    pub body_parser: Option<BodyParserConfig>,
+
+    pub response_limit: Option<ResponseLimit>,
+
+    pub external_resolver: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum BodyParserConfig {
+    /// `bodyParser: false`
+    Bool(bool),
+    /// `bodyParser: { sizeLimit: '1mb' }`
+    #[serde(rename_all = "camelCase")]
+    Options { size_limit: Option<SizeLimit> },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ResponseLimit {
+    /// `responseLimit: false`
+    Bool(bool),
+    Size(SizeLimit),
+}
+
+/// A number of bytes, or a string like `'4mb'`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum SizeLimit {
+    Bytes(f64),
+    Str(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Runtime {
+    #[serde(rename = "nodejs")]
+    Nodejs,
+    #[serde(rename = "edge")]
+    Edge,
+    #[serde(rename = "experimental-edge")]
+    ExperimentalEdge,
+}
+
+impl Runtime {
+    pub fn is_edge(self) -> bool {
+        matches!(self, Runtime::Edge | Runtime::ExperimentalEdge)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Regions {
+    /// `regions: 'all'`, `regions: 'home'` or a single region
+    Str(String),
+    List(Vec<String>),
+}
@@ -0,0 +1,267 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use swc_common::Span;
+use swc_ecmascript::ast::*;
+use swc_ecmascript::visit::{noop_fold_type, Fold};
+
+use crate::diagnostics::{emit_error, FULL_PACKAGE_IMPORT, INVALID_IMPORT_TEMPLATE};
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageConfig {
+    /// Path of the module a member is imported from, like
+    /// `lodash/{{member}}`.
+    ///
+    /// `{{member}}` is replaced by the name of the member. The name can be
+    /// converted with `{{kebabCase member}}`, `{{camelCase member}}`,
+    /// `{{lowerCase member}}` or `{{upperCase member}}`.
+    pub transform: String,
+
+    /// Report an error for imports of the whole package, like
+    /// `import _ from 'lodash'`.
+    #[serde(default)]
+    pub prevent_full_import: bool,
+
+    /// Import members by name instead of as the default export of their
+    /// module.
+    #[serde(default)]
+    pub skip_default_conversion: bool,
+}
+
+/// A package in [Config].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Package {
+    Config(PackageConfig),
+    /// `false` leaves the imports of a built-in package as they are. `true` is
+    /// the same as not listing the package.
+    Enabled(bool),
+}
+
+/// Packages whose named imports are rewritten, keyed by their name.
+pub type Config = HashMap<String, Package>;
+
+/// Packages which are rewritten unless the config lists them.
+fn builtin_packages() -> HashMap<String, PackageConfig> {
+    let mut packages = HashMap::new();
+    for (name, transform) in [
+        ("lodash", "lodash/{{member}}"),
+        ("@mui/icons-material", "@mui/icons-material/{{member}}"),
+        ("date-fns", "date-fns/{{member}}"),
+    ] {
+        packages.insert(
+            name.to_string(),
+            PackageConfig {
+                transform: transform.to_string(),
+                prevent_full_import: false,
+                skip_default_conversion: false,
+            },
+        );
+    }
+
+    packages
+}
+
+/// Rewrites `import { debounce } from 'lodash'` into
+/// `import debounce from 'lodash/debounce'`, so only the used members of large
+/// packages are compiled and bundled.
+pub fn modularize_imports(config: &Config) -> impl Fold + '_ {
+    ModularizeImports {
+        config,
+        builtin: builtin_packages(),
+    }
+}
+
+struct ModularizeImports<'a> {
+    config: &'a Config,
+    builtin: HashMap<String, PackageConfig>,
+}
+
+impl ModularizeImports<'_> {
+    fn package(&self, src: &str) -> Option<&PackageConfig> {
+        match self.config.get(src) {
+            Some(Package::Config(package)) => Some(package),
+            Some(Package::Enabled(false)) => None,
+            Some(Package::Enabled(true)) | None => self.builtin.get(src),
+        }
+    }
+
+    /// Returns the imports which replace `decl`, or `None` if it is left as is.
+    fn rewrite(&self, decl: &ImportDecl) -> Option<Vec<ImportDecl>> {
+        if decl.type_only {
+            return None;
+        }
+        let package = self.package(&decl.src.value)?;
+
+        let mut imports = vec![];
+        let mut rest = vec![];
+        for specifier in &decl.specifiers {
+            let named = match specifier {
+                // `import { default as _ }` imports the whole package too.
+                ImportSpecifier::Named(named)
+                    if !named.is_type_only && imported_name(named) != "default" =>
+                {
+                    named
+                }
+                _ => {
+                    if package.prevent_full_import {
+                        emit_error(
+                            specifier_span(specifier),
+                            FULL_PACKAGE_IMPORT,
+                            &format!(
+                                "The whole `{}` package is imported. Import its members by name \
+                                 instead.",
+                                decl.src.value
+                            ),
+                        );
+                    }
+                    rest.push(specifier.clone());
+                    continue;
+                }
+            };
+
+            let src = match render(&package.transform, imported_name(named)) {
+                Ok(src) => src,
+                Err(message) => {
+                    emit_error(named.span, INVALID_IMPORT_TEMPLATE, &message);
+                    return None;
+                }
+            };
+
+            let specifier = if package.skip_default_conversion {
+                ImportSpecifier::Named(named.clone())
+            } else {
+                ImportSpecifier::Default(ImportDefaultSpecifier {
+                    span: named.span,
+                    local: named.local.clone(),
+                })
+            };
+            imports.push(ImportDecl {
+                span: decl.span,
+                specifiers: vec![specifier],
+                src: Str {
+                    span: decl.src.span,
+                    value: src.into(),
+                    has_escape: false,
+                    kind: Default::default(),
+                },
+                type_only: false,
+                asserts: None,
+            });
+        }
+
+        if imports.is_empty() {
+            return None;
+        }
+        if !rest.is_empty() {
+            imports.insert(
+                0,
+                ImportDecl {
+                    specifiers: rest,
+                    ..decl.clone()
+                },
+            );
+        }
+
+        Some(imports)
+    }
+}
+
+fn imported_name(named: &ImportNamedSpecifier) -> &str {
+    match &named.imported {
+        Some(ModuleExportName::Ident(imported)) => &*imported.sym,
+        Some(ModuleExportName::Str(imported)) => &*imported.value,
+        None => &*named.local.sym,
+    }
+}
+
+fn specifier_span(specifier: &ImportSpecifier) -> Span {
+    match specifier {
+        ImportSpecifier::Named(s) => s.span,
+        ImportSpecifier::Default(s) => s.span,
+        ImportSpecifier::Namespace(s) => s.span,
+    }
+}
+
+/// Replaces the `{{...}}` placeholders of `template`.
+fn render(template: &str, member: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start..].find("}}") {
+            Some(end) => start + end,
+            None => return Err(format!("Unclosed `{{{{` in `{}`.", template)),
+        };
+        out.push_str(&rest[..start]);
+
+        let value = match rest[start + 2..end].split_whitespace().collect::<Vec<_>>()[..] {
+            ["member"] => member.to_string(),
+            ["kebabCase", "member"] => kebab_case(member),
+            ["camelCase", "member"] => camel_case(member),
+            ["lowerCase", "member"] => member.to_lowercase(),
+            ["upperCase", "member"] => member.to_uppercase(),
+            _ => {
+                return Err(format!(
+                    "Unknown placeholder `{}` in `{}`.",
+                    &rest[start..end + 2],
+                    template
+                ))
+            }
+        };
+        out.push_str(&value);
+        rest = &rest[end + 2..];
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+/// Like lodash's `kebabCase`, a run of capitals is one word: `HTMLElement`
+/// becomes `html-element`.
+fn kebab_case(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).map_or(false, |next| next.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+                out.push('-');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+
+    out
+}
+
+fn camel_case(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+impl Fold for ModularizeImports<'_> {
+    noop_fold_type!();
+
+    fn fold_module_items(&mut self, items: Vec<ModuleItem>) -> Vec<ModuleItem> {
+        let mut new_items = Vec::with_capacity(items.len());
+        for item in items {
+            match &item {
+                ModuleItem::ModuleDecl(ModuleDecl::Import(decl)) => match self.rewrite(decl) {
+                    Some(imports) => new_items.extend(
+                        imports
+                            .into_iter()
+                            .map(|decl| ModuleItem::ModuleDecl(ModuleDecl::Import(decl))),
+                    ),
+                    None => new_items.push(item),
+                },
+                _ => new_items.push(item),
+            }
+        }
+
+        new_items
+    }
+}
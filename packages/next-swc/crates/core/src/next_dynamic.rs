@@ -8,18 +8,19 @@ use swc_ecmascript::ast::{
     ExprOrSpread, Ident, ImportDecl, ImportSpecifier, KeyValueProp, Lit, MemberExpr, MemberProp,
     Null, ObjectLit, Prop, PropName, PropOrSpread, Str, StrKind,
 };
+use swc_ecmascript::utils::ident::{Id, IdentLike};
 use swc_ecmascript::utils::ExprFactory;
-use swc_ecmascript::utils::{
-    ident::{Id, IdentLike},
-    HANDLER,
-};
 use swc_ecmascript::visit::{Fold, FoldWith};
 
+use crate::diagnostics::{emit_error, INVALID_DYNAMIC_ARGUMENTS, INVALID_DYNAMIC_OPTIONS_TYPE};
+use crate::metadata::{DynamicImportMetadata, SharedMetadata};
+
 pub fn next_dynamic(
     is_development: bool,
     is_server: bool,
     filename: FileName,
     pages_dir: Option<PathBuf>,
+    metadata: SharedMetadata,
 ) -> impl Fold {
     NextDynamicPatcher {
         is_development,
@@ -29,6 +30,7 @@ pub fn next_dynamic(
         dynamic_bindings: vec![],
         is_next_dynamic_first_arg: false,
         dynamically_imported_specifier: None,
+        metadata,
     }
 }
 
@@ -41,6 +43,7 @@ struct NextDynamicPatcher {
     dynamic_bindings: Vec<Id>,
     is_next_dynamic_first_arg: bool,
     dynamically_imported_specifier: Option<String>,
+    metadata: SharedMetadata,
 }
 
 impl Fold for NextDynamicPatcher {
@@ -75,38 +78,30 @@ impl Fold for NextDynamicPatcher {
             if let Expr::Ident(identifier) = &**i {
                 if self.dynamic_bindings.contains(&identifier.to_id()) {
                     if expr.args.is_empty() {
-                        HANDLER.with(|handler| {
-                            handler
-                                .struct_span_err(
-                                    identifier.span,
-                                    "next/dynamic requires at least one argument",
-                                )
-                                .emit()
-                        });
+                        emit_error(
+                            identifier.span,
+                            INVALID_DYNAMIC_ARGUMENTS,
+                            "next/dynamic requires at least one argument",
+                        );
                         return expr;
                     } else if expr.args.len() > 2 {
-                        HANDLER.with(|handler| {
-                            handler
-                                .struct_span_err(
-                                    identifier.span,
-                                    "next/dynamic only accepts 2 arguments",
-                                )
-                                .emit()
-                        });
+                        emit_error(
+                            identifier.span,
+                            INVALID_DYNAMIC_ARGUMENTS,
+                            "next/dynamic only accepts 2 arguments",
+                        );
                         return expr;
                     }
                     if expr.args.len() == 2 {
                         match &*expr.args[1].expr {
                             Expr::Object(_) => {}
                             _ => {
-                                HANDLER.with(|handler| {
-                          handler
-                              .struct_span_err(
-                                  identifier.span,
-                                  "next/dynamic options must be an object literal.\nRead more: https://nextjs.org/docs/messages/invalid-dynamic-options-type",
-                              )
-                              .emit();
-                      });
+                                emit_error(
+                                    identifier.span,
+                                    INVALID_DYNAMIC_OPTIONS_TYPE,
+                                    "next/dynamic options must be an object literal.\nRead more: \
+                                     https://nextjs.org/docs/messages/invalid-dynamic-options-type",
+                                );
                                 return expr;
                             }
                         }
@@ -289,7 +284,16 @@ impl Fold for NextDynamicPatcher {
                     } else {
                         expr.args.push(second_arg)
                     }
-                    self.dynamically_imported_specifier = None;
+
+                    if let Some(specifier) = self.dynamically_imported_specifier.take() {
+                        self.metadata
+                            .borrow_mut()
+                            .next_dynamic
+                            .push(DynamicImportMetadata {
+                                specifier,
+                                ssr: !has_ssr_false,
+                            });
+                    }
                 }
             }
         }
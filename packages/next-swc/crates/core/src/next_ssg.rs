@@ -1,21 +1,35 @@
 use easy_error::{bail, Error};
 use fxhash::FxHashSet;
 use std::mem::take;
+use swc_atoms::JsWord;
+use swc_common::errors::HANDLER;
 use swc_common::pass::{Repeat, Repeated};
-use swc_common::DUMMY_SP;
+use swc_common::{Span, DUMMY_SP};
 use swc_ecmascript::ast::*;
 use swc_ecmascript::utils::ident::IdentLike;
 use swc_ecmascript::visit::FoldWith;
 use swc_ecmascript::{
-    utils::{Id, HANDLER},
+    utils::Id,
     visit::{noop_fold_type, Fold},
 };
 
+use crate::diagnostics::{
+    emit_error, emit_error_with_fix, emit_warning, Suggestion, SSG_IMPORT_RETAINED,
+    SSG_WITH_SERVER_SIDE_PROPS,
+};
+use crate::metadata::{BindingKind, RemovedBinding, RetainedImport, SharedMetadata, SsgMetadata};
+
 /// Note: This paths requires running `resolver` **before** running this.
-pub fn next_ssg() -> impl Fold {
+///
+/// The removed bindings are reported in the metadata. If
+/// `warn_retained_imports` is true, imports which are used by data functions
+/// but cannot be removed are also reported as warnings.
+pub fn next_ssg(warn_retained_imports: bool, metadata: SharedMetadata) -> impl Fold {
     Repeat::new(NextSsg {
         state: Default::default(),
         in_lhs_of_var: false,
+        warn_retained_imports,
+        metadata,
     })
 }
 
@@ -36,10 +50,18 @@ struct State {
 
     cur_declaring: FxHashSet<Id>,
 
+    /// Span of the `export function` or `export const` being analyzed, used to
+    /// suggest removing `getServerSideProps`.
+    cur_export_decl: Option<Span>,
+    server_props_decl: Option<Span>,
+
     is_prerenderer: bool,
     is_server_props: bool,
     done: bool,
 
+    /// Bindings removed by all the runs so far.
+    removed: Vec<RemovedBinding>,
+
     should_run_again: bool,
 }
 
@@ -50,30 +72,17 @@ impl State {
         if ssg_exports.contains(&&*i.sym) {
             if &*i.sym == "getServerSideProps" {
                 if self.is_prerenderer {
-                    HANDLER.with(|handler| {
-                        handler
-                            .struct_span_err(
-                                i.span,
-                                "You can not use getStaticProps or getStaticPaths with \
-                                 getServerSideProps. To use SSG, please remove getServerSideProps",
-                            )
-                            .emit()
-                    });
+                    report_ssg_with_server_side_props(i.span, self.cur_export_decl);
                     bail!("both ssg and ssr functions present");
                 }
 
                 self.is_server_props = true;
+                if self.server_props_decl.is_none() {
+                    self.server_props_decl = self.cur_export_decl;
+                }
             } else {
                 if self.is_server_props {
-                    HANDLER.with(|handler| {
-                        handler
-                            .struct_span_err(
-                                i.span,
-                                "You can not use getStaticProps or getStaticPaths with \
-                                 getServerSideProps. To use SSG, please remove getServerSideProps",
-                            )
-                            .emit()
-                    });
+                    report_ssg_with_server_side_props(i.span, self.server_props_decl);
                     bail!("both ssg and ssr functions present");
                 }
 
@@ -87,6 +96,24 @@ impl State {
     }
 }
 
+fn report_ssg_with_server_side_props(span: Span, server_props_decl: Option<Span>) {
+    let message = "You can not use getStaticProps or getStaticPaths with getServerSideProps. To \
+                   use SSG, please remove getServerSideProps";
+
+    match server_props_decl {
+        Some(decl) => emit_error_with_fix(
+            span,
+            SSG_WITH_SERVER_SIDE_PROPS,
+            message,
+            Suggestion {
+                message: "Remove getServerSideProps".into(),
+                edits: vec![(decl, String::new())],
+            },
+        ),
+        None => emit_error(span, SSG_WITH_SERVER_SIDE_PROPS, message),
+    }
+}
+
 struct Analyzer<'a> {
     state: &'a mut State,
     in_lhs_of_var: bool,
@@ -210,8 +237,18 @@ impl Fold for Analyzer<'_> {
             _ => {}
         };
 
+        let old_export_decl = self.state.cur_export_decl.take();
+        if let ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(e)) = &s {
+            match &e.decl {
+                Decl::Fn(..) => self.state.cur_export_decl = Some(e.span),
+                Decl::Var(v) if v.decls.len() == 1 => self.state.cur_export_decl = Some(e.span),
+                _ => {}
+            }
+        }
+
         // Visit children to ensure that all references is added to the scope.
         let s = s.fold_children_with(self);
+        self.state.cur_export_decl = old_export_decl;
 
         if let ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(e)) = &s {
             match &e.decl {
@@ -289,6 +326,8 @@ impl Fold for Analyzer<'_> {
 struct NextSsg {
     state: State,
     in_lhs_of_var: bool,
+    warn_retained_imports: bool,
+    metadata: SharedMetadata,
 }
 
 impl NextSsg {
@@ -296,6 +335,75 @@ impl NextSsg {
         self.state.refs_from_data_fn.contains(&id) && !self.state.refs_from_other.contains(&id)
     }
 
+    fn record_removed(&mut self, name: &JsWord, kind: BindingKind, source: Option<&JsWord>) {
+        self.state.removed.push(RemovedBinding {
+            name: name.to_string(),
+            kind,
+            source: source.map(|source| source.to_string()),
+        });
+    }
+
+    /// Imports of `m` which are used by data functions and by other code.
+    ///
+    /// This must be called after the last run, when both sets of references
+    /// are complete.
+    fn retained_imports(&self, m: &Module) -> Vec<(RetainedImport, Span)> {
+        let mut retained = vec![];
+        for item in &m.body {
+            let import = match item {
+                ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => import,
+                _ => continue,
+            };
+            for specifier in &import.specifiers {
+                let local = match specifier {
+                    ImportSpecifier::Named(ImportNamedSpecifier { local, .. })
+                    | ImportSpecifier::Default(ImportDefaultSpecifier { local, .. })
+                    | ImportSpecifier::Namespace(ImportStarAsSpecifier { local, .. }) => local,
+                };
+                let id = local.to_id();
+                if self.state.refs_from_data_fn.contains(&id)
+                    && self.state.refs_from_other.contains(&id)
+                {
+                    retained.push((
+                        RetainedImport {
+                            name: local.sym.to_string(),
+                            source: import.src.value.to_string(),
+                        },
+                        local.span,
+                    ));
+                }
+            }
+        }
+
+        retained
+    }
+
+    fn report(&self, m: &Module) {
+        let retained = self.retained_imports(m);
+        if self.warn_retained_imports {
+            for (import, span) in &retained {
+                HANDLER.with(|handler| {
+                    emit_warning(
+                        handler,
+                        *span,
+                        SSG_IMPORT_RETAINED,
+                        &format!(
+                            "`{}` from '{}' is used by a data function, but it's also used by \
+                             code which runs in the browser, so it's included in the client \
+                             bundle.",
+                            import.name, import.source
+                        ),
+                    )
+                });
+            }
+        }
+
+        let mut metadata = self.metadata.borrow_mut();
+        metadata.next_ssg.removed = self.state.removed.clone();
+        metadata.next_ssg.retained_imports =
+            retained.into_iter().map(|(import, _)| import).collect();
+    }
+
     /// Mark identifiers in `n` as a candidate for removal.
     fn mark_as_candidate<N>(&mut self, n: N) -> N
     where
@@ -343,7 +451,8 @@ impl Fold for NextSsg {
             return i;
         }
 
-        i.specifiers.retain(|s| match s {
+        let mut specifiers = take(&mut i.specifiers);
+        specifiers.retain(|s| match s {
             ImportSpecifier::Named(ImportNamedSpecifier { local, .. })
             | ImportSpecifier::Default(ImportDefaultSpecifier { local, .. })
             | ImportSpecifier::Namespace(ImportStarAsSpecifier { local, .. }) => {
@@ -354,6 +463,7 @@ impl Fold for NextSsg {
                         local.span.ctxt
                     );
 
+                    self.record_removed(&local.sym, BindingKind::Import, Some(&i.src.value));
                     self.state.should_run_again = true;
                     false
                 } else {
@@ -361,6 +471,7 @@ impl Fold for NextSsg {
                 }
             }
         });
+        i.specifiers = specifiers;
 
         i
     }
@@ -382,7 +493,18 @@ impl Fold for NextSsg {
         //     return m;
         // }
 
-        m.fold_children_with(self)
+        self.metadata.borrow_mut().next_ssg = SsgMetadata {
+            is_ssg: self.state.is_prerenderer,
+            is_ssp: self.state.is_server_props,
+            ..Default::default()
+        };
+
+        let m = m.fold_children_with(self);
+        if !self.state.should_run_again {
+            self.report(&m);
+        }
+
+        m
     }
 
     fn fold_module_item(&mut self, i: ModuleItem) -> ModuleItem {
@@ -474,9 +596,14 @@ impl Fold for NextSsg {
         items
     }
 
+    /// Drops the specifiers exporting a data function, including re-exports
+    /// like `export { fetchData as getServerSideProps } from '../lib/data'`.
     fn fold_named_export(&mut self, mut n: NamedExport) -> NamedExport {
         n.specifiers = n.specifiers.fold_with(self);
 
+        // The original names of re-exports are not local bindings, so they
+        // have nothing to remove.
+        let is_re_export = n.src.is_some();
         n.specifiers.retain(|s| {
             let preserve = match s {
                 ExportSpecifier::Namespace(ExportNamespaceSpecifier {
@@ -512,7 +639,9 @@ impl Fold for NextSsg {
                     }) = s
                     {
                         self.state.should_run_again = true;
-                        self.state.refs_from_data_fn.insert(orig.to_id());
+                        if !is_re_export {
+                            self.state.refs_from_data_fn.insert(orig.to_id());
+                        }
                     }
 
                     false
@@ -533,6 +662,7 @@ impl Fold for NextSsg {
             match &mut p {
                 Pat::Ident(name) => {
                     if self.should_remove(name.id.to_id()) {
+                        self.record_removed(&name.id.sym, BindingKind::Variable, None);
                         self.state.should_run_again = true;
                         tracing::trace!(
                             "Dropping var `{}{:?}` because it should be removed",
@@ -566,6 +696,11 @@ impl Fold for NextSsg {
                                 }
                                 ObjectPatProp::Assign(prop) => {
                                     if self.should_remove(prop.key.to_id()) {
+                                        self.record_removed(
+                                            &prop.key.sym,
+                                            BindingKind::Variable,
+                                            None,
+                                        );
                                         self.mark_as_candidate(prop.value);
 
                                         None
@@ -605,6 +740,7 @@ impl Fold for NextSsg {
         match s {
             Stmt::Decl(Decl::Fn(f)) => {
                 if self.should_remove(f.ident.to_id()) {
+                    self.record_removed(&f.ident.sym, BindingKind::Function, None);
                     self.mark_as_candidate(f.function);
                     return Stmt::Empty(EmptyStmt { span: DUMMY_SP });
                 }
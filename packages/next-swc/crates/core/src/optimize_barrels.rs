@@ -0,0 +1,462 @@
+//! Rewrites imports of barrel files, modules like
+//!
+//! ```js
+//! export { Button } from './button'
+//! export { default as Card } from './card'
+//! ```
+//!
+//! to import each member from the module which defines it, so the modules
+//! which are not used don't have to be compiled.
+//!
+//! A barrel is only optimized if it contains nothing but imports and
+//! re-exports. Listing a package in [Config::packages] asserts that the modules
+//! it re-exports can be imported on their own, because the other modules are
+//! not evaluated anymore.
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use swc_atoms::{js_word, JsWord};
+use swc_common::{FileName, SourceMap, DUMMY_SP};
+use swc_ecma_loader::resolve::Resolve;
+use swc_ecma_loader::resolvers::node::NodeModulesResolver;
+use swc_ecma_loader::TargetEnv;
+use swc_ecmascript::ast::*;
+use swc_ecmascript::parser::{lexer::Lexer, EsConfig, Parser, StringInput, Syntax, TsConfig};
+use swc_ecmascript::visit::{noop_fold_type, Fold};
+
+use crate::metadata::{SharedMetadata, UnoptimizedBarrel};
+use crate::shake_exports::exported_name;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    /// Import sources whose barrel files are optimized, like `@acme/ui`.
+    pub packages: Vec<String>,
+}
+
+/// Where a member of a barrel comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReExport {
+    /// `export { name } from 'src'`
+    Named { src: JsWord, name: JsWord },
+    /// `export * as ns from 'src'`
+    Namespace { src: JsWord },
+}
+
+/// Members re-exported by name by a barrel file.
+#[derive(Clone, Debug, Default)]
+pub struct Barrel {
+    pub exports: HashMap<JsWord, ReExport>,
+}
+
+/// Returns the members re-exported by `module`, or why it is not a barrel.
+pub fn analyze_barrel(module: &Module) -> Result<Barrel, String> {
+    // Bindings created by imports, which are re-exported with
+    // `export { foo }`.
+    let mut imports = HashMap::new();
+    let mut barrel = Barrel::default();
+
+    for item in &module.body {
+        match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(decl)) => {
+                if decl.specifiers.is_empty() {
+                    return Err(format!("`import '{}'` has side effects", decl.src.value));
+                }
+
+                for specifier in &decl.specifiers {
+                    let (local, re_export) = match specifier {
+                        ImportSpecifier::Named(named) => (
+                            &named.local,
+                            ReExport::Named {
+                                src: decl.src.value.clone(),
+                                name: match &named.imported {
+                                    Some(ModuleExportName::Ident(imported)) => imported.sym.clone(),
+                                    Some(ModuleExportName::Str(imported)) => imported.value.clone(),
+                                    None => named.local.sym.clone(),
+                                },
+                            },
+                        ),
+                        ImportSpecifier::Default(default) => (
+                            &default.local,
+                            ReExport::Named {
+                                src: decl.src.value.clone(),
+                                name: js_word!("default"),
+                            },
+                        ),
+                        ImportSpecifier::Namespace(namespace) => (
+                            &namespace.local,
+                            ReExport::Namespace {
+                                src: decl.src.value.clone(),
+                            },
+                        ),
+                    };
+                    imports.insert(local.sym.clone(), re_export);
+                }
+            }
+
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(export)) => {
+                for specifier in &export.specifiers {
+                    match (specifier, &export.src) {
+                        (ExportSpecifier::Named(named), Some(src)) => {
+                            let orig = match &named.orig {
+                                ModuleExportName::Ident(orig) => orig.sym.clone(),
+                                ModuleExportName::Str(orig) => orig.value.clone(),
+                            };
+                            if let Some(exported) = exported_name(named) {
+                                barrel.exports.insert(
+                                    exported.clone(),
+                                    ReExport::Named {
+                                        src: src.value.clone(),
+                                        name: orig,
+                                    },
+                                );
+                            }
+                        }
+                        (ExportSpecifier::Namespace(namespace), Some(src)) => {
+                            if let ModuleExportName::Ident(name) = &namespace.name {
+                                barrel.exports.insert(
+                                    name.sym.clone(),
+                                    ReExport::Namespace {
+                                        src: src.value.clone(),
+                                    },
+                                );
+                            }
+                        }
+                        (ExportSpecifier::Named(named), None) => {
+                            let orig = match &named.orig {
+                                ModuleExportName::Ident(orig) => &orig.sym,
+                                ModuleExportName::Str(orig) => &orig.value,
+                            };
+                            let re_export = match imports.get(orig) {
+                                Some(re_export) => re_export.clone(),
+                                None => {
+                                    return Err(format!("`{}` is declared in the barrel", orig))
+                                }
+                            };
+                            if let Some(exported) = exported_name(named) {
+                                barrel.exports.insert(exported.clone(), re_export);
+                            }
+                        }
+                        _ => return Err("the barrel has an unsupported export".into()),
+                    }
+                }
+            }
+
+            // The members of `export *` are only known after reading the module, so
+            // they are not optimized, but the other members are.
+            ModuleItem::ModuleDecl(ModuleDecl::ExportAll(..)) => {}
+
+            // Directives like `'use strict'`
+            ModuleItem::Stmt(Stmt::Expr(ExprStmt { expr, .. }))
+                if matches!(&**expr, Expr::Lit(Lit::Str(..))) => {}
+
+            _ => return Err("the barrel contains code which may have side effects".into()),
+        }
+    }
+
+    Ok(barrel)
+}
+
+/// Number of barrels in [BARRELS], after which it is cleared.
+const MAX_BARRELS: usize = 1024;
+
+/// Analyzed barrels, with the time they were modified.
+static BARRELS: Lazy<Mutex<HashMap<PathBuf, (SystemTime, Arc<Result<Barrel, String>>)>>> =
+    Lazy::new(Default::default);
+
+fn load_barrel(path: &Path) -> Arc<Result<Barrel, String>> {
+    let modified = match path.metadata().and_then(|m| m.modified()) {
+        Ok(modified) => modified,
+        Err(err) => return Arc::new(Err(format!("failed to read {}: {}", path.display(), err))),
+    };
+    if let Some((cached, barrel)) = BARRELS.lock().unwrap().get(path) {
+        if *cached == modified {
+            return barrel.clone();
+        }
+    }
+
+    let barrel = Arc::new(parse_barrel(path).and_then(|module| analyze_barrel(&module)));
+    let mut barrels = BARRELS.lock().unwrap();
+    // Barrels which were moved or deleted are never looked up again.
+    if barrels.len() >= MAX_BARRELS && !barrels.contains_key(path) {
+        barrels.clear();
+    }
+    barrels.insert(path.to_path_buf(), (modified, barrel.clone()));
+
+    barrel
+}
+
+fn parse_barrel(path: &Path) -> Result<Module, String> {
+    let cm = SourceMap::default();
+    let fm = cm
+        .load_file(path)
+        .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+
+    let syntax = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("ts") => Syntax::Typescript(Default::default()),
+        Some("tsx") => Syntax::Typescript(TsConfig {
+            tsx: true,
+            ..Default::default()
+        }),
+        _ => Syntax::Es(EsConfig {
+            jsx: true,
+            ..Default::default()
+        }),
+    };
+    let lexer = Lexer::new(syntax, EsVersion::latest(), StringInput::from(&*fm), None);
+
+    Parser::new_from(lexer)
+        .parse_module()
+        .map_err(|_| format!("failed to parse {}", path.display()))
+}
+
+pub fn optimize_barrels(
+    config: &Config,
+    file_name: FileName,
+    is_server: bool,
+    metadata: SharedMetadata,
+) -> impl Fold + '_ {
+    let target_env = if is_server {
+        TargetEnv::Node
+    } else {
+        TargetEnv::Browser
+    };
+
+    OptimizeBarrels {
+        config,
+        file_name,
+        resolver: NodeModulesResolver::new(target_env, Default::default(), true),
+        metadata,
+    }
+}
+
+struct OptimizeBarrels<'a> {
+    config: &'a Config,
+    file_name: FileName,
+    resolver: NodeModulesResolver,
+    metadata: SharedMetadata,
+}
+
+impl OptimizeBarrels<'_> {
+    fn report(&self, src: &str, reason: String) {
+        self.metadata
+            .borrow_mut()
+            .unoptimized_barrels
+            .push(UnoptimizedBarrel {
+                source: src.to_string(),
+                reason,
+            });
+    }
+
+    /// Returns the imports which replace `decl`, or `None` if it is left as is.
+    fn rewrite(&self, decl: &ImportDecl) -> Option<Vec<ImportDecl>> {
+        let src = &*decl.src.value;
+        if decl.type_only || !self.config.packages.iter().any(|p| p == src) {
+            return None;
+        }
+
+        let path = match self.resolver.resolve(&self.file_name, src) {
+            Ok(FileName::Real(path)) => path,
+            Ok(..) => return None,
+            Err(err) => {
+                self.report(src, format!("failed to resolve: {}", err));
+                return None;
+            }
+        };
+        let barrel = load_barrel(&path);
+        let barrel = match &*barrel {
+            Ok(barrel) => barrel,
+            Err(reason) => {
+                self.report(src, reason.clone());
+                return None;
+            }
+        };
+
+        let mut imports = vec![];
+        let mut rest = vec![];
+        for specifier in &decl.specifiers {
+            let import = match specifier {
+                ImportSpecifier::Named(named) if named.is_type_only => None,
+                ImportSpecifier::Named(named) => {
+                    let name = match &named.imported {
+                        Some(ModuleExportName::Ident(imported)) => &imported.sym,
+                        Some(ModuleExportName::Str(imported)) => &imported.value,
+                        None => &named.local.sym,
+                    };
+                    self.import_member(decl, &path, barrel, name, &named.local)
+                }
+                ImportSpecifier::Default(default) => {
+                    self.import_member(decl, &path, barrel, &js_word!("default"), &default.local)
+                }
+                ImportSpecifier::Namespace(..) => {
+                    self.report(src, "`import * as` uses every member".into());
+                    None
+                }
+            };
+
+            match import {
+                Some(import) => imports.push(import),
+                None => rest.push(specifier.clone()),
+            }
+        }
+
+        if imports.is_empty() {
+            return None;
+        }
+        if !rest.is_empty() {
+            imports.insert(
+                0,
+                ImportDecl {
+                    specifiers: rest,
+                    ..decl.clone()
+                },
+            );
+        }
+
+        Some(imports)
+    }
+
+    /// Returns the import of `name` from the module which defines it, or
+    /// `None` if it is imported from the barrel.
+    fn import_member(
+        &self,
+        decl: &ImportDecl,
+        barrel_path: &Path,
+        barrel: &Barrel,
+        name: &JsWord,
+        local: &Ident,
+    ) -> Option<ImportDecl> {
+        let re_export = match barrel.exports.get(name) {
+            Some(re_export) => re_export,
+            None => {
+                self.report(
+                    &decl.src.value,
+                    format!("`{}` is not re-exported by name", name),
+                );
+                return None;
+            }
+        };
+        let local = local.clone();
+        let (src, specifier) = match re_export {
+            ReExport::Named { src, name } if *name == js_word!("default") => (
+                src,
+                ImportSpecifier::Default(ImportDefaultSpecifier {
+                    span: DUMMY_SP,
+                    local,
+                }),
+            ),
+            ReExport::Named { src, name } => (
+                src,
+                ImportSpecifier::Named(ImportNamedSpecifier {
+                    span: DUMMY_SP,
+                    imported: if *name == local.sym {
+                        None
+                    } else {
+                        Some(ModuleExportName::Ident(Ident::new(name.clone(), DUMMY_SP)))
+                    },
+                    local,
+                    is_type_only: false,
+                }),
+            ),
+            ReExport::Namespace { src } => (
+                src,
+                ImportSpecifier::Namespace(ImportStarAsSpecifier {
+                    span: DUMMY_SP,
+                    local,
+                }),
+            ),
+        };
+
+        let src = match self.import_path(barrel_path, src) {
+            Ok(src) => src,
+            Err(reason) => {
+                self.report(&decl.src.value, reason);
+                return None;
+            }
+        };
+
+        Some(ImportDecl {
+            span: decl.span,
+            specifiers: vec![specifier],
+            src: Str {
+                span: decl.src.span,
+                value: src.into(),
+                has_escape: false,
+                kind: Default::default(),
+            },
+            type_only: false,
+            asserts: None,
+        })
+    }
+
+    /// Resolves `src` of `barrel` and returns how the file being transformed
+    /// imports the same module.
+    ///
+    /// A package keeps its name if the file resolves it to the same module.
+    /// Otherwise the module is imported by its path relative to the file.
+    fn import_path(&self, barrel: &Path, src: &str) -> Result<String, String> {
+        let target = match self
+            .resolver
+            .resolve(&FileName::Real(barrel.to_path_buf()), src)
+        {
+            Ok(FileName::Real(target)) => target,
+            // Built-in modules, like `fs`.
+            Ok(..) => return Ok(src.to_string()),
+            Err(err) => {
+                return Err(format!(
+                    "failed to resolve `{}` re-exported by the barrel: {}",
+                    src, err
+                ))
+            }
+        };
+        if !src.starts_with('.') && !src.starts_with('/') {
+            if let Ok(FileName::Real(resolved)) = self.resolver.resolve(&self.file_name, src) {
+                if resolved == target {
+                    return Ok(src.to_string());
+                }
+            }
+        }
+
+        let relative = match &self.file_name {
+            FileName::Real(file) => file
+                .parent()
+                .and_then(|dir| pathdiff::diff_paths(&target, dir))
+                .unwrap_or(target),
+            _ => target,
+        };
+
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        if relative.starts_with('.') || relative.starts_with('/') {
+            Ok(relative)
+        } else {
+            Ok(format!("./{}", relative))
+        }
+    }
+}
+
+impl Fold for OptimizeBarrels<'_> {
+    noop_fold_type!();
+
+    fn fold_module_items(&mut self, items: Vec<ModuleItem>) -> Vec<ModuleItem> {
+        let mut new_items = Vec::with_capacity(items.len());
+        for item in items {
+            match &item {
+                ModuleItem::ModuleDecl(ModuleDecl::Import(decl)) => match self.rewrite(decl) {
+                    Some(imports) => new_items.extend(
+                        imports
+                            .into_iter()
+                            .map(|decl| ModuleItem::ModuleDecl(ModuleDecl::Import(decl))),
+                    ),
+                    None => new_items.push(item),
+                },
+                _ => new_items.push(item),
+            }
+        }
+
+        new_items
+    }
+}
@@ -1,46 +1,84 @@
-use chrono::Utc;
-use swc_common::{Span, DUMMY_SP};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::env;
+use swc_atoms::JsWord;
+use swc_common::{Span, Spanned, DUMMY_SP};
 use swc_ecmascript::ast::*;
-use swc_ecmascript::utils::HANDLER;
 use swc_ecmascript::visit::{Fold, FoldWith};
 
-pub fn page_config(is_development: bool, is_page_file: bool) -> impl Fold {
+use crate::diagnostics::{emit_error, emit_error_with_fix, Suggestion, INVALID_PAGE_CONFIG};
+use crate::metadata::{
+    AmpConfig, ApiConfig, BodyParserConfig, PageConfigMetadata, Regions, ResponseLimit, Runtime,
+    SharedMetadata, SizeLimit,
+};
+use crate::shake_exports::exported_name;
+
+/// `src` is the source of the file, which the marker of dropped client files
+/// is derived from unless `source_date_epoch` is given.
+pub fn page_config(
+    is_development: bool,
+    is_page_file: bool,
+    src: &str,
+    source_date_epoch: Option<&str>,
+    metadata: SharedMetadata,
+) -> impl Fold {
     PageConfig {
         is_development,
         is_page_file,
+        drop_marker_suffix: drop_marker_suffix(source_date_epoch, src),
+        metadata,
         ..Default::default()
     }
 }
 
 pub fn page_config_test() -> impl Fold {
     PageConfig {
-        in_test: true,
         is_page_file: true,
+        drop_marker_suffix: String::from("mock_timestamp"),
         ..Default::default()
     }
 }
 
+/// Returns `source_date_epoch`, falling back to `SOURCE_DATE_EPOCH`, or the
+/// first 16 digits of the SHA-1 of `src` if neither is set, so the output of
+/// the pass only depends on its input.
+fn drop_marker_suffix(source_date_epoch: Option<&str>, src: &str) -> String {
+    let epoch = source_date_epoch
+        .map(String::from)
+        .or_else(|| env::var("SOURCE_DATE_EPOCH").ok());
+    match epoch {
+        Some(epoch) if !epoch.trim().is_empty() => epoch.trim().to_string(),
+        _ => hex::encode(Sha1::digest(src.as_bytes()))[..16].to_string(),
+    }
+}
+
 #[derive(Debug, Default)]
 struct PageConfig {
     drop_bundle: bool,
-    in_test: bool,
+    drop_marker_suffix: String,
     is_development: bool,
     is_page_file: bool,
+    local_configs: HashMap<JsWord, VarDeclarator>,
+    metadata: SharedMetadata,
 }
 
 const STRING_LITERAL_DROP_BUNDLE: &str = "__NEXT_DROP_CLIENT_FILE__";
 const CONFIG_KEY: &str = "config";
 
 impl Fold for PageConfig {
+    fn fold_module(&mut self, module: Module) -> Module {
+        self.local_configs = local_configs(&module);
+
+        module.fold_children_with(self)
+    }
+
     fn fold_module_items(&mut self, items: Vec<ModuleItem>) -> Vec<ModuleItem> {
         let mut new_items = vec![];
         for item in items {
             new_items.push(item.fold_with(self));
             if !self.is_development && self.drop_bundle {
-                let timestamp = match self.in_test {
-                    true => String::from("mock_timestamp"),
-                    false => Utc::now().timestamp().to_string(),
-                };
                 return vec![ModuleItem::Stmt(Stmt::Decl(Decl::Var(VarDecl {
                     decls: vec![VarDeclarator {
                         name: Pat::Ident(BindingIdent {
@@ -52,7 +90,11 @@ impl Fold for PageConfig {
                             type_ann: None,
                         }),
                         init: Some(Box::new(Expr::Lit(Lit::Str(Str {
-                            value: format!("{} {}", STRING_LITERAL_DROP_BUNDLE, timestamp).into(),
+                            value: format!(
+                                "{} {}",
+                                STRING_LITERAL_DROP_BUNDLE, self.drop_marker_suffix
+                            )
+                            .into(),
                             span: DUMMY_SP,
                             kind: StrKind::Synthesized {},
                             has_escape: false,
@@ -73,105 +115,336 @@ impl Fold for PageConfig {
     fn fold_export_decl(&mut self, export: ExportDecl) -> ExportDecl {
         if let Decl::Var(var_decl) = &export.decl {
             for decl in &var_decl.decls {
-                let mut is_config = false;
+                if matches!(&decl.name, Pat::Ident(ident) if &ident.id.sym == CONFIG_KEY) {
+                    self.handle_config_decl(decl);
+                }
+            }
+        }
+        export
+    }
+
+    fn fold_named_export(&mut self, export: NamedExport) -> NamedExport {
+        for specifier in &export.specifiers {
+            let named = match specifier {
+                ExportSpecifier::Named(named) => named,
+                _ => continue,
+            };
+            if !exported_name(named).map_or(false, |name| name == CONFIG_KEY) {
+                continue;
+            }
+
+            // `export { config }` of a local binding is analyzed like
+            // `export const config`.
+            let decl = match (&export.src, &named.orig) {
+                (None, ModuleExportName::Ident(orig)) => self.local_configs.get(&orig.sym).cloned(),
+                _ => None,
+            };
+            match decl {
+                Some(decl) => self.handle_config_decl(&decl),
+                None => self.handle_error("Config cannot be re-exported.", named.span),
+            }
+        }
+        export
+    }
+}
+
+/// Declarations of the top-level variables exported as `config` with
+/// `export { config }` or `export { pageConfig as config }`.
+fn local_configs(module: &Module) -> HashMap<JsWord, VarDeclarator> {
+    let mut names = vec![];
+    for item in &module.body {
+        if let ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport {
+            src: None,
+            specifiers,
+            ..
+        })) = item
+        {
+            for specifier in specifiers {
+                if let ExportSpecifier::Named(named) = specifier {
+                    if let ModuleExportName::Ident(orig) = &named.orig {
+                        if exported_name(named).map_or(false, |name| name == CONFIG_KEY) {
+                            names.push(orig.sym.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut decls = HashMap::new();
+    for item in &module.body {
+        if let ModuleItem::Stmt(Stmt::Decl(Decl::Var(var_decl))) = item {
+            for decl in &var_decl.decls {
                 if let Pat::Ident(ident) = &decl.name {
-                    if &ident.id.sym == CONFIG_KEY {
-                        is_config = true;
+                    if names.contains(&ident.id.sym) {
+                        decls.insert(ident.id.sym.clone(), decl.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    decls
+}
+
+impl PageConfig {
+    fn handle_config_decl(&mut self, decl: &VarDeclarator) {
+        match decl.init.as_deref().map(unwrap_expr) {
+            Some(Expr::Object(obj)) => {
+                let config = self.parse_config(obj);
+                if config.amp == Some(AmpConfig::Bool(true)) && self.is_page_file {
+                    self.drop_bundle = true;
+                }
+                self.metadata.borrow_mut().page_config = Some(config);
+            }
+            Some(init) => self.handle_error("Expected config to be an object.", init.span()),
+            None => self.handle_error("Expected config to be an object.", decl.span),
+        }
+    }
+
+    fn handle_error(&mut self, details: &str, span: Span) {
+        if self.is_page_file {
+            emit_error(span, INVALID_PAGE_CONFIG, &error_message(details));
+        }
+    }
+
+    fn handle_error_with_fix(&mut self, details: &str, span: Span, suggestion: Suggestion) {
+        if self.is_page_file {
+            emit_error_with_fix(
+                span,
+                INVALID_PAGE_CONFIG,
+                &error_message(details),
+                suggestion,
+            );
+        }
+    }
+
+    /// Reports the properties of `obj` which are not literals, and returns the
+    /// key, the span of the key and the value of the others.
+    fn properties<'a>(&mut self, obj: &'a ObjectLit) -> Vec<(String, Span, &'a Expr)> {
+        let mut props = vec![];
+        for (i, prop) in obj.props.iter().enumerate() {
+            let prop = match prop {
+                PropOrSpread::Prop(prop) => prop,
+                PropOrSpread::Spread(spread) => {
+                    self.handle_error_with_fix(
+                        "Property spread is not allowed.",
+                        spread.dot3_token.with_hi(spread.expr.span().hi),
+                        Suggestion {
+                            message: "Remove the spread".into(),
+                            edits: vec![(removal_span(&obj.props, i), String::new())],
+                        },
+                    );
+                    continue;
+                }
+            };
+
+            match &**prop {
+                Prop::KeyValue(kv) => match &kv.key {
+                    PropName::Ident(key) => props.push((key.sym.to_string(), key.span, &*kv.value)),
+                    PropName::Str(key) => props.push((key.value.to_string(), key.span, &*kv.value)),
+                    key => self.handle_error("Invalid property found.", key.span()),
+                },
+                prop => self.handle_error("Invalid property or value.", prop.span()),
+            }
+        }
+
+        props
+    }
+
+    fn parse_config(&mut self, obj: &ObjectLit) -> PageConfigMetadata {
+        let mut config = PageConfigMetadata::default();
+        for (key, key_span, value) in self.properties(obj) {
+            match &*key {
+                "amp" => {
+                    config.amp = match unwrap_expr(value) {
+                        Expr::Lit(Lit::Bool(b)) => Some(AmpConfig::Bool(b.value)),
+                        Expr::Lit(Lit::Str(s)) if &s.value == "hybrid" => {
+                            Some(AmpConfig::Str(s.value.to_string()))
+                        }
+                        _ => {
+                            self.handle_error("`amp` must be a boolean or 'hybrid'.", value.span());
+                            None
+                        }
                     }
                 }
+                "api" => {
+                    config.api = match unwrap_expr(value) {
+                        Expr::Object(obj) => Some(self.parse_api_config(obj)),
+                        _ => {
+                            self.handle_error("`api` must be an object.", value.span());
+                            None
+                        }
+                    }
+                }
+                "runtime" => {
+                    config.runtime = match unwrap_expr(value) {
+                        Expr::Lit(Lit::Str(s)) if &s.value == "nodejs" => Some(Runtime::Nodejs),
+                        Expr::Lit(Lit::Str(s)) if &s.value == "edge" => Some(Runtime::Edge),
+                        Expr::Lit(Lit::Str(s)) if &s.value == "experimental-edge" => {
+                            Some(Runtime::ExperimentalEdge)
+                        }
+                        _ => {
+                            self.handle_error(
+                                "`runtime` must be 'nodejs', 'edge' or 'experimental-edge'.",
+                                value.span(),
+                            );
+                            None
+                        }
+                    }
+                }
+                "regions" => config.regions = self.parse_regions(value),
+                "unstable_runtimeJS" => {
+                    config.unstable_runtime_js = self.parse_bool("unstable_runtimeJS", value)
+                }
+                "unstable_JsPreload" => {
+                    config.unstable_js_preload = self.parse_bool("unstable_JsPreload", value)
+                }
+                _ => self.handle_error(&format!("Unknown property `{}`.", key), key_span),
+            }
+        }
 
-                if is_config {
-                    if let Some(expr) = &decl.init {
-                        if let Expr::Object(obj) = &**expr {
-                            for prop in &obj.props {
-                                if let PropOrSpread::Prop(prop) = prop {
-                                    if let Prop::KeyValue(kv) = &**prop {
-                                        match &kv.key {
-                                            PropName::Ident(ident) => {
-                                                if &ident.sym == "amp" {
-                                                    if let Expr::Lit(Lit::Bool(Bool {
-                                                        value,
-                                                        ..
-                                                    })) = &*kv.value
-                                                    {
-                                                        if *value && self.is_page_file {
-                                                            self.drop_bundle = true;
-                                                        }
-                                                    } else if let Expr::Lit(Lit::Str(_)) =
-                                                        &*kv.value
-                                                    {
-                                                        // Do not replace
-                                                        // bundle
-                                                    } else {
-                                                        self.handle_error(
-                                                            "Invalid value found.",
-                                                            export.span,
-                                                        );
-                                                    }
-                                                }
-                                            }
-                                            _ => {
-                                                self.handle_error(
-                                                    "Invalid property found.",
-                                                    export.span,
-                                                );
-                                            }
-                                        }
-                                    } else {
-                                        self.handle_error(
-                                            "Invalid property or value.",
-                                            export.span,
-                                        );
-                                    }
+        config
+    }
+
+    fn parse_api_config(&mut self, obj: &ObjectLit) -> ApiConfig {
+        let mut config = ApiConfig::default();
+        for (key, key_span, value) in self.properties(obj) {
+            match &*key {
+                "bodyParser" => {
+                    config.body_parser = match unwrap_expr(value) {
+                        Expr::Lit(Lit::Bool(b)) => Some(BodyParserConfig::Bool(b.value)),
+                        Expr::Object(obj) => {
+                            let mut size_limit = None;
+                            for (key, key_span, value) in self.properties(obj) {
+                                if key == "sizeLimit" {
+                                    size_limit =
+                                        self.parse_size_limit("api.bodyParser.sizeLimit", value);
                                 } else {
                                     self.handle_error(
-                                        "Property spread is not allowed.",
-                                        export.span,
+                                        &format!("Unknown property `api.bodyParser.{}`.", key),
+                                        key_span,
                                     );
                                 }
                             }
-                        } else {
-                            self.handle_error("Expected config to be an object.", export.span);
+                            Some(BodyParserConfig::Options { size_limit })
+                        }
+                        _ => {
+                            self.handle_error(
+                                "`api.bodyParser` must be a boolean or an object.",
+                                value.span(),
+                            );
+                            None
                         }
-                    } else {
-                        self.handle_error("Expected config to be an object.", export.span);
                     }
                 }
+                "responseLimit" => {
+                    config.response_limit = match unwrap_expr(value) {
+                        Expr::Lit(Lit::Bool(b)) => Some(ResponseLimit::Bool(b.value)),
+                        _ => self
+                            .parse_size_limit("api.responseLimit", value)
+                            .map(ResponseLimit::Size),
+                    }
+                }
+                "externalResolver" => {
+                    config.external_resolver = self.parse_bool("api.externalResolver", value)
+                }
+                _ => self.handle_error(&format!("Unknown property `api.{}`.", key), key_span),
             }
         }
-        export
+
+        config
     }
 
-    fn fold_export_named_specifier(
-        &mut self,
-        specifier: ExportNamedSpecifier,
-    ) -> ExportNamedSpecifier {
-        match &specifier.exported {
-            Some(ident) => {
-                if let ModuleExportName::Ident(ident) = ident {
-                    if &ident.sym == CONFIG_KEY {
-                        self.handle_error("Config cannot be re-exported.", specifier.span)
-                    }
-                }
+    fn parse_size_limit(&mut self, name: &str, value: &Expr) -> Option<SizeLimit> {
+        static SIZE: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"(?i)^\d+(\.\d+)?\s*(b|kb|mb|gb|tb|pb)$").unwrap());
+
+        match unwrap_expr(value) {
+            Expr::Lit(Lit::Num(n)) if n.value >= 0.0 => Some(SizeLimit::Bytes(n.value)),
+            Expr::Lit(Lit::Str(s)) if SIZE.is_match(&s.value) => {
+                Some(SizeLimit::Str(s.value.to_string()))
+            }
+            _ => {
+                self.handle_error(
+                    &format!("`{}` must be a number of bytes or a size like '1mb'.", name),
+                    value.span(),
+                );
+                None
             }
-            None => {
-                if let ModuleExportName::Ident(ident) = &specifier.orig {
-                    if &ident.sym == CONFIG_KEY {
-                        self.handle_error("Config cannot be re-exported.", specifier.span)
+        }
+    }
+
+    fn parse_regions(&mut self, value: &Expr) -> Option<Regions> {
+        match unwrap_expr(value) {
+            Expr::Lit(Lit::Str(s)) => Some(Regions::Str(s.value.to_string())),
+            Expr::Array(array) => {
+                let mut regions = vec![];
+                for elem in &array.elems {
+                    match elem.as_ref().map(|elem| (elem, unwrap_expr(&elem.expr))) {
+                        Some((ExprOrSpread { spread: None, .. }, Expr::Lit(Lit::Str(s)))) => {
+                            regions.push(s.value.to_string())
+                        }
+                        Some((elem, _)) => {
+                            self.handle_error("Regions must be strings.", elem.expr.span())
+                        }
+                        None => self.handle_error("Regions must be strings.", array.span),
                     }
                 }
+                Some(Regions::List(regions))
+            }
+            _ => {
+                self.handle_error(
+                    "`regions` must be a string or an array of strings.",
+                    value.span(),
+                );
+                None
             }
         }
-        specifier
     }
-}
 
-impl PageConfig {
-    fn handle_error(&mut self, details: &str, span: Span) {
-        if self.is_page_file {
-            let message = format!("Invalid page config export found. {} \
-      See: https://nextjs.org/docs/messages/invalid-page-config", details);
-            HANDLER.with(|handler| handler.struct_span_err(span, &message).emit());
+    fn parse_bool(&mut self, name: &str, value: &Expr) -> Option<bool> {
+        match unwrap_expr(value) {
+            Expr::Lit(Lit::Bool(b)) => Some(b.value),
+            _ => {
+                self.handle_error(&format!("`{}` must be a boolean.", name), value.span());
+                None
+            }
         }
     }
 }
+
+/// Returns the expression wrapped by parentheses and TypeScript expressions
+/// like `as const`.
+fn unwrap_expr(e: &Expr) -> &Expr {
+    match e {
+        Expr::Paren(ParenExpr { expr, .. })
+        | Expr::TsConstAssertion(TsConstAssertion { expr, .. })
+        | Expr::TsAs(TsAsExpr { expr, .. })
+        | Expr::TsTypeAssertion(TsTypeAssertion { expr, .. })
+        | Expr::TsNonNull(TsNonNullExpr { expr, .. }) => unwrap_expr(expr),
+        _ => e,
+    }
+}
+
+fn error_message(details: &str) -> String {
+    format!(
+        "Invalid page config export found. {} See: \
+         https://nextjs.org/docs/messages/invalid-page-config",
+        details
+    )
+}
+
+/// Span of `props[idx]` together with the comma separating it from its
+/// neighbours.
+fn removal_span(props: &[PropOrSpread], idx: usize) -> Span {
+    let span = props[idx].span();
+
+    if let Some(next) = props.get(idx + 1) {
+        span.with_hi(next.span().lo)
+    } else if idx > 0 {
+        span.with_lo(props[idx - 1].span().hi)
+    } else {
+        span
+    }
+}
@@ -0,0 +1,494 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use swc_atoms::{js_word, JsWord};
+use swc_common::errors::HANDLER;
+use swc_common::pass::Optional;
+use swc_common::{FileName, Span, Spanned};
+use swc_ecmascript::ast::*;
+use swc_ecmascript::utils::find_ids;
+use swc_ecmascript::visit::{noop_fold_type, noop_visit_type, Fold, Visit, VisitWith};
+
+use crate::diagnostics::{
+    emit_error, emit_warning, COMPONENT_IN_API_ROUTE, GET_INITIAL_PROPS_WITH_SERVER_SIDE_PROPS,
+    GET_STATIC_PATHS_WITHOUT_GET_STATIC_PROPS, PAGE_WITHOUT_VALID_COMPONENT, UNKNOWN_PAGE_EXPORT,
+};
+use crate::shake_exports::module_export_name;
+
+/// Reports the exports of a page which Next.js cannot handle, like a page
+/// without a component or an API route which exports one.
+///
+/// Files are classified by their path relative to `pages_dir`. Without it, the
+/// path is taken relative to the last `pages` directory in it. Files which
+/// cannot be classified are not checked.
+pub fn page_exports(
+    file_name: &FileName,
+    pages_dir: Option<&Path>,
+    is_page_file: bool,
+) -> impl Fold {
+    let kind = match (file_name, pages_dir) {
+        (FileName::Real(path), Some(pages_dir)) => {
+            path.strip_prefix(pages_dir).ok().map(PageKind::from_path)
+        }
+        (FileName::Real(path), None) => {
+            let components = path.components().collect::<Vec<_>>();
+            components
+                .iter()
+                .rposition(|c| c.as_os_str() == "pages")
+                .map(|i| PageKind::from_path(&components[i + 1..].iter().collect::<PathBuf>()))
+        }
+        _ => None,
+    };
+
+    Optional::new(PageExports { kind }, is_page_file)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PageKind {
+    Page,
+    App,
+    Document,
+    Api,
+    Middleware,
+}
+
+impl PageKind {
+    /// `path` is relative to the pages directory.
+    fn from_path(path: &Path) -> Self {
+        let stem = path.file_stem().and_then(|stem| stem.to_str());
+        let is_top_level = path.parent() == Some(Path::new(""));
+
+        if stem == Some("_middleware") {
+            PageKind::Middleware
+        } else if path.starts_with("api") || (is_top_level && stem == Some("api")) {
+            PageKind::Api
+        } else if is_top_level && stem == Some("_app") {
+            PageKind::App
+        } else if is_top_level && stem == Some("_document") {
+            PageKind::Document
+        } else {
+            PageKind::Page
+        }
+    }
+
+    /// Named exports which Next.js uses.
+    fn known_exports(self) -> &'static [&'static str] {
+        match self {
+            PageKind::Page => &[
+                "config",
+                "getStaticProps",
+                "getStaticPaths",
+                "getServerSideProps",
+            ],
+            PageKind::App => &["reportWebVitals"],
+            PageKind::Api => &["config"],
+            PageKind::Document | PageKind::Middleware => &[],
+        }
+    }
+}
+
+struct PageExports {
+    /// `None` if the file is not known to be in the pages directory.
+    kind: Option<PageKind>,
+}
+
+/// A value bound to a top-level name or exported by default.
+#[derive(Clone, Copy)]
+enum Value<'a> {
+    Fn(&'a Function),
+    Class(&'a Class),
+    Expr(&'a Expr),
+    /// A re-export or a declaration without an initializer.
+    Unknown,
+}
+
+struct Export<'a> {
+    name: JsWord,
+    span: Span,
+    value: Value<'a>,
+}
+
+/// Top-level functions, classes and variables, by name.
+fn declarations(module: &Module) -> HashMap<JsWord, Value<'_>> {
+    let mut values = HashMap::new();
+    for item in &module.body {
+        let decl = match item {
+            ModuleItem::Stmt(Stmt::Decl(decl))
+            | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. })) => decl,
+            _ => continue,
+        };
+        match decl {
+            Decl::Fn(f) => {
+                values.insert(f.ident.sym.clone(), Value::Fn(&f.function));
+            }
+            Decl::Class(c) => {
+                values.insert(c.ident.sym.clone(), Value::Class(&c.class));
+            }
+            Decl::Var(v) => {
+                for decl in &v.decls {
+                    if let Pat::Ident(ident) = &decl.name {
+                        let value = decl.init.as_deref().map_or(Value::Unknown, Value::Expr);
+                        values.insert(ident.id.sym.clone(), value);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    values
+}
+
+/// Returns the value exports of `module`, including the default export.
+fn exports<'a>(module: &'a Module, declarations: &HashMap<JsWord, Value<'a>>) -> Vec<Export<'a>> {
+    let resolve = |sym: &JsWord| declarations.get(sym).copied().unwrap_or(Value::Unknown);
+
+    let mut exports = vec![];
+    for item in &module.body {
+        let decl = match item {
+            ModuleItem::ModuleDecl(decl) => decl,
+            ModuleItem::Stmt(..) => continue,
+        };
+
+        match decl {
+            ModuleDecl::ExportDecl(ExportDecl { decl, .. }) => match decl {
+                Decl::Fn(f) => exports.push(Export {
+                    name: f.ident.sym.clone(),
+                    span: f.ident.span,
+                    value: Value::Fn(&f.function),
+                }),
+                Decl::Class(c) => exports.push(Export {
+                    name: c.ident.sym.clone(),
+                    span: c.ident.span,
+                    value: Value::Class(&c.class),
+                }),
+                Decl::Var(v) => {
+                    let ids: Vec<Ident> = find_ids(&v.decls);
+                    exports.extend(ids.into_iter().map(|id| Export {
+                        value: resolve(&id.sym),
+                        name: id.sym,
+                        span: id.span,
+                    }));
+                }
+                Decl::TsEnum(e) => exports.push(Export {
+                    name: e.id.sym.clone(),
+                    span: e.id.span,
+                    value: Value::Unknown,
+                }),
+                _ => {}
+            },
+            ModuleDecl::ExportNamed(export) if !export.type_only => {
+                for specifier in &export.specifiers {
+                    match specifier {
+                        ExportSpecifier::Named(named) => {
+                            let name = named.exported.as_ref().unwrap_or(&named.orig);
+                            let value = match (&export.src, &named.orig) {
+                                (None, ModuleExportName::Ident(orig)) => resolve(&orig.sym),
+                                _ => Value::Unknown,
+                            };
+                            exports.push(Export {
+                                name: module_export_name(name).clone(),
+                                span: named.span,
+                                value,
+                            });
+                        }
+                        ExportSpecifier::Namespace(ExportNamespaceSpecifier { name, span }) => {
+                            exports.push(Export {
+                                name: module_export_name(name).clone(),
+                                span: *span,
+                                value: Value::Unknown,
+                            })
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            ModuleDecl::ExportDefaultDecl(export) => {
+                let (ident, value) = match &export.decl {
+                    DefaultDecl::Fn(f) => (&f.ident, Value::Fn(&f.function)),
+                    DefaultDecl::Class(c) => (&c.ident, Value::Class(&c.class)),
+                    DefaultDecl::TsInterfaceDecl(..) => continue,
+                };
+                exports.push(Export {
+                    name: js_word!("default"),
+                    span: ident.as_ref().map_or(export.span, |ident| ident.span),
+                    value,
+                });
+            }
+            ModuleDecl::ExportDefaultExpr(export) => {
+                let value = match &*export.expr {
+                    Expr::Ident(ident) => resolve(&ident.sym),
+                    expr => Value::Expr(expr),
+                };
+                exports.push(Export {
+                    name: js_word!("default"),
+                    span: export.expr.span(),
+                    value,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    exports
+}
+
+fn unwrap_parens(e: &Expr) -> &Expr {
+    match e {
+        Expr::Paren(ParenExpr { expr, .. }) => unwrap_parens(expr),
+        _ => e,
+    }
+}
+
+/// Returns true if `value` cannot be a React component, like a string or an
+/// object literal.
+fn is_not_component(value: Value) -> bool {
+    match value {
+        Value::Expr(e) => matches!(
+            unwrap_parens(e),
+            Expr::Lit(..) | Expr::Object(..) | Expr::Array(..) | Expr::Tpl(..)
+        ),
+        _ => false,
+    }
+}
+
+/// Returns true if `value` is a function which returns JSX, or a class
+/// component.
+fn is_component(value: Value) -> bool {
+    match value {
+        Value::Fn(f) => returns_jsx(&f.body),
+        Value::Class(c) => is_class_component(c),
+        Value::Expr(e) => match unwrap_parens(e) {
+            Expr::Fn(f) => returns_jsx(&f.function.body),
+            Expr::Class(c) => is_class_component(&c.class),
+            Expr::Arrow(ArrowExpr { body, .. }) => match body {
+                BlockStmtOrExpr::BlockStmt(body) => returns_jsx(body),
+                BlockStmtOrExpr::Expr(body) => is_jsx(body),
+            },
+            // Higher-order components, like `memo(() => <div />)`.
+            Expr::Call(CallExpr { args, .. }) => args
+                .iter()
+                .any(|arg| arg.spread.is_none() && is_component(Value::Expr(&*arg.expr))),
+            _ => false,
+        },
+        Value::Unknown => false,
+    }
+}
+
+fn is_jsx(e: &Expr) -> bool {
+    matches!(
+        unwrap_parens(e),
+        Expr::JSXElement(..) | Expr::JSXFragment(..)
+    )
+}
+
+fn returns_jsx<N: VisitWith<ReturnsJsx>>(body: &N) -> bool {
+    let mut visitor = ReturnsJsx(false);
+    body.visit_with(&mut visitor);
+    visitor.0
+}
+
+/// `class Page extends Component` or `class Page extends React.PureComponent`.
+fn is_class_component(class: &Class) -> bool {
+    let super_class = match class.super_class.as_deref() {
+        Some(super_class) => super_class,
+        None => return false,
+    };
+    let name = match super_class {
+        Expr::Ident(i) => &i.sym,
+        Expr::Member(MemberExpr {
+            prop: MemberProp::Ident(i),
+            ..
+        }) => &i.sym,
+        _ => return false,
+    };
+
+    &**name == "Component" || &**name == "PureComponent"
+}
+
+/// Finds `return <jsx />` in a function body, excluding nested functions.
+struct ReturnsJsx(bool);
+
+impl Visit for ReturnsJsx {
+    noop_visit_type!();
+
+    fn visit_return_stmt(&mut self, stmt: &ReturnStmt) {
+        if stmt.arg.as_deref().map_or(false, is_jsx) {
+            self.0 = true;
+        }
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+
+    fn visit_class(&mut self, _: &Class) {}
+}
+
+fn assigned_member(left: &PatOrExpr) -> Option<&MemberExpr> {
+    let left = match left {
+        PatOrExpr::Expr(e) => &**e,
+        PatOrExpr::Pat(p) => match &**p {
+            Pat::Expr(e) => &**e,
+            _ => return None,
+        },
+    };
+    match left {
+        Expr::Member(member) => Some(member),
+        _ => None,
+    }
+}
+
+fn prop_name_is(key: &PropName, name: &str) -> bool {
+    match key {
+        PropName::Ident(i) => &*i.sym == name,
+        PropName::Str(s) => &*s.value == name,
+        _ => false,
+    }
+}
+
+/// Spans of `Page.getInitialProps = ...` and `static getInitialProps` in
+/// top-level classes.
+fn get_initial_props(module: &Module) -> Vec<Span> {
+    let mut spans = vec![];
+    let mut visit_class = |class: &Class| {
+        for member in &class.body {
+            match member {
+                ClassMember::Method(m)
+                    if m.is_static && prop_name_is(&m.key, "getInitialProps") =>
+                {
+                    spans.push(m.key.span())
+                }
+                ClassMember::ClassProp(p)
+                    if p.is_static && prop_name_is(&p.key, "getInitialProps") =>
+                {
+                    spans.push(p.key.span())
+                }
+                _ => {}
+            }
+        }
+    };
+
+    for item in &module.body {
+        match item {
+            ModuleItem::Stmt(Stmt::Decl(Decl::Class(c)))
+            | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+                decl: Decl::Class(c),
+                ..
+            })) => visit_class(&c.class),
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(ExportDefaultDecl {
+                decl: DefaultDecl::Class(c),
+                ..
+            })) => visit_class(&c.class),
+            ModuleItem::Stmt(Stmt::Expr(ExprStmt { expr, .. })) => {
+                if let Expr::Assign(AssignExpr { left, .. }) = &**expr {
+                    match assigned_member(left) {
+                        Some(MemberExpr {
+                            span,
+                            prop: MemberProp::Ident(prop),
+                            ..
+                        }) if &*prop.sym == "getInitialProps" => spans.push(*span),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    spans
+}
+
+impl PageExports {
+    fn check(&self, module: &Module) {
+        let kind = match self.kind {
+            Some(PageKind::Middleware) | None => return,
+            Some(kind) => kind,
+        };
+
+        let declarations = declarations(module);
+        let exports = exports(module, &declarations);
+        let find = |name: &str| exports.iter().find(|export| &*export.name == name);
+
+        let known = kind.known_exports();
+        for export in &exports {
+            if export.name != js_word!("default") && !known.contains(&&*export.name) {
+                HANDLER.with(|handler| {
+                    emit_warning(
+                        handler,
+                        export.span,
+                        UNKNOWN_PAGE_EXPORT,
+                        &format!(
+                            "`{}` is not a page export known by Next.js, so it's bundled with \
+                             the page. Move it to another module.\nRead more: \
+                             https://nextjs.org/docs/messages/unknown-page-export",
+                            export.name
+                        ),
+                    )
+                });
+            }
+        }
+
+        if kind == PageKind::Api {
+            for export in &exports {
+                if is_component(export.value) {
+                    emit_error(
+                        export.span,
+                        COMPONENT_IN_API_ROUTE,
+                        "API routes cannot export React components. Move the component to a \
+                         page.\nRead more: https://nextjs.org/docs/messages/component-in-api-route",
+                    );
+                }
+            }
+            return;
+        }
+
+        match find("default") {
+            Some(export) if is_not_component(export.value) => emit_error(
+                export.span,
+                PAGE_WITHOUT_VALID_COMPONENT,
+                "The default export of a page must be a React component.\nRead more: \
+                 https://nextjs.org/docs/messages/page-without-valid-component",
+            ),
+            Some(..) => {}
+            None => emit_error(
+                module.span.shrink_to_lo(),
+                PAGE_WITHOUT_VALID_COMPONENT,
+                "A page must export a React component by default.\nRead more: \
+                 https://nextjs.org/docs/messages/page-without-valid-component",
+            ),
+        }
+
+        if let (Some(export), None) = (find("getStaticPaths"), find("getStaticProps")) {
+            emit_error(
+                export.span,
+                GET_STATIC_PATHS_WITHOUT_GET_STATIC_PROPS,
+                "`getStaticPaths` cannot be used without `getStaticProps`.\nRead more: \
+                 https://nextjs.org/docs/messages/getstaticpaths-without-getstaticprops",
+            );
+        }
+
+        if find("getServerSideProps").is_some() {
+            let spans = get_initial_props(module)
+                .into_iter()
+                .chain(find("getInitialProps").map(|export| export.span));
+            for span in spans {
+                emit_error(
+                    span,
+                    GET_INITIAL_PROPS_WITH_SERVER_SIDE_PROPS,
+                    "`getInitialProps` cannot be used together with `getServerSideProps`. Please \
+                     remove `getInitialProps`.\nRead more: \
+                     https://nextjs.org/docs/messages/get-initial-props-with-server-side-props",
+                );
+            }
+        }
+    }
+}
+
+impl Fold for PageExports {
+    noop_fold_type!();
+
+    fn fold_module(&mut self, module: Module) -> Module {
+        self.check(&module);
+        module
+    }
+}
@@ -0,0 +1,223 @@
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::fmt;
+use swc_ecmascript::ast::{Module, Program, Script};
+use swc_ecmascript::visit::Fold;
+
+use crate::trace;
+
+/// A pass of [crate::custom_before_pass].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PassName {
+    DisallowReExportAllInPage,
+    PageExports,
+    StyledJsx,
+    HookOptimizer,
+    ModularizeImports,
+    StyledComponents,
+    Define,
+    TypeofWindow,
+    OptimizeBarrels,
+    ServerComponents,
+    EnvironmentImports,
+    NextSsg,
+    AmpAttributes,
+    NextDynamic,
+    PageConfig,
+    EdgeRuntime,
+    Relay,
+    RemoveConsole,
+    ReactRemoveProperties,
+    ShakeExports,
+    Plugins,
+}
+
+impl PassName {
+    /// Passes which run when `pipeline.passes` is not set, in order.
+    pub const DEFAULT: [PassName; 21] = [
+        PassName::DisallowReExportAllInPage,
+        PassName::PageExports,
+        PassName::StyledJsx,
+        PassName::HookOptimizer,
+        PassName::ModularizeImports,
+        PassName::StyledComponents,
+        PassName::Define,
+        PassName::TypeofWindow,
+        PassName::OptimizeBarrels,
+        PassName::ServerComponents,
+        PassName::NextSsg,
+        PassName::EnvironmentImports,
+        PassName::AmpAttributes,
+        PassName::NextDynamic,
+        PassName::PageConfig,
+        PassName::EdgeRuntime,
+        PassName::Relay,
+        PassName::RemoveConsole,
+        PassName::ReactRemoveProperties,
+        PassName::ShakeExports,
+        PassName::Plugins,
+    ];
+
+    /// Returns true if the output of the pass does not depend on the target, so
+    /// it is run by [crate::shared_before_pass].
+    pub fn is_shared(self) -> bool {
+        matches!(
+            self,
+            PassName::DisallowReExportAllInPage
+                | PassName::PageExports
+                | PassName::StyledJsx
+                | PassName::HookOptimizer
+                | PassName::ModularizeImports
+                | PassName::StyledComponents
+        )
+    }
+
+    /// Passes which must run before this one if both are in the pipeline.
+    pub fn dependencies(self) -> &'static [PassName] {
+        match self {
+            // The code removed by these passes must not count as a use of the data
+            // functions or of the exports.
+            PassName::NextSsg => &[PassName::Define, PassName::TypeofWindow],
+            PassName::ShakeExports => {
+                &[PassName::Define, PassName::TypeofWindow, PassName::NextSsg]
+            }
+            // Modules like `fs` are imported for the data functions, which are
+            // removed from the client build.
+            PassName::EnvironmentImports => &[PassName::NextSsg],
+            // The runtime is read from the config found by `page_config`.
+            PassName::EdgeRuntime => &[PassName::PageConfig],
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for PassName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PassName::DisallowReExportAllInPage => "disallow_re_export_all_in_page",
+            PassName::PageExports => "page_exports",
+            PassName::StyledJsx => "styled_jsx",
+            PassName::HookOptimizer => "hook_optimizer",
+            PassName::ModularizeImports => "modularize_imports",
+            PassName::StyledComponents => "styled_components",
+            PassName::Define => "define",
+            PassName::TypeofWindow => "typeof_window",
+            PassName::OptimizeBarrels => "optimize_barrels",
+            PassName::ServerComponents => "server_components",
+            PassName::EnvironmentImports => "environment_imports",
+            PassName::NextSsg => "next_ssg",
+            PassName::AmpAttributes => "amp_attributes",
+            PassName::NextDynamic => "next_dynamic",
+            PassName::PageConfig => "page_config",
+            PassName::EdgeRuntime => "edge_runtime",
+            PassName::Relay => "relay",
+            PassName::RemoveConsole => "remove_console",
+            PassName::ReactRemoveProperties => "react_remove_properties",
+            PassName::ShakeExports => "shake_exports",
+            PassName::Plugins => "plugins",
+        };
+
+        f.write_str(name)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PipelineConfig {
+    /// Passes to run, in order.
+    #[serde(default)]
+    passes: Option<Vec<PassName>>,
+
+    /// Passes removed from `passes`.
+    #[serde(default)]
+    disable: Vec<PassName>,
+}
+
+/// Validated list of the passes to run.
+///
+/// Passes which need a config, like `styled_components`, also need it to be
+/// set in [crate::TransformOptions] to run.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "PipelineConfig")]
+pub struct Pipeline {
+    passes: Vec<PassName>,
+}
+
+impl Pipeline {
+    pub fn new(passes: Vec<PassName>) -> Result<Self, String> {
+        for (i, &pass) in passes.iter().enumerate() {
+            if passes[..i].contains(&pass) {
+                return Err(format!("`{}` is listed more than once", pass));
+            }
+
+            // The shared passes are applied once to the program before it's cloned for
+            // each target.
+            if pass.is_shared() {
+                if let Some(prev) = passes[..i].iter().find(|prev| !prev.is_shared()) {
+                    return Err(format!("`{}` should run before `{}`", pass, prev));
+                }
+            }
+
+            if let Some(dep) = pass
+                .dependencies()
+                .iter()
+                .find(|dep| passes[i + 1..].contains(dep))
+            {
+                return Err(format!("`{}` should run before `{}`", dep, pass));
+            }
+        }
+
+        Ok(Pipeline { passes })
+    }
+
+    pub fn contains(&self, pass: PassName) -> bool {
+        self.passes.contains(&pass)
+    }
+
+    pub fn passes(&self) -> impl Iterator<Item = PassName> + '_ {
+        self.passes.iter().copied()
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Pipeline {
+            passes: PassName::DEFAULT.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<PipelineConfig> for Pipeline {
+    type Error = String;
+
+    fn try_from(config: PipelineConfig) -> Result<Self, Self::Error> {
+        let mut passes = config.passes.unwrap_or_else(|| PassName::DEFAULT.to_vec());
+        passes.retain(|pass| !config.disable.contains(pass));
+
+        Pipeline::new(passes)
+    }
+}
+
+/// Runs boxed passes in order, each in its own trace span.
+pub(crate) struct Passes<'a>(pub Vec<(PassName, Box<dyn Fold + 'a>)>);
+
+impl Fold for Passes<'_> {
+    fn fold_program(&mut self, program: Program) -> Program {
+        self.0.iter_mut().fold(program, |program, (name, pass)| {
+            trace::span(&name.to_string(), || pass.fold_program(program))
+        })
+    }
+
+    fn fold_module(&mut self, module: Module) -> Module {
+        self.0.iter_mut().fold(module, |module, (name, pass)| {
+            trace::span(&name.to_string(), || pass.fold_module(module))
+        })
+    }
+
+    fn fold_script(&mut self, script: Script) -> Script {
+        self.0.iter_mut().fold(script, |script, (name, pass)| {
+            trace::span(&name.to_string(), || pass.fold_script(script))
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use anyhow::{bail, Context, Error};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use swc_common::{FileName, Spanned};
+use swc_ecmascript::ast::{Module, Program, Script};
+use swc_ecmascript::visit::Fold;
+use wasmer::{imports, Instance, Memory, Module as WasmModule, Store};
+
+use super::PluginConfig;
+use crate::diagnostics::{emit_error, PLUGIN_FAILED};
+
+/// Information about the file being transformed.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginContext {
+    filename: String,
+    is_server: bool,
+    is_development: bool,
+}
+
+#[derive(Serialize)]
+struct PluginInput<'a> {
+    program: &'a Program,
+    config: &'a serde_json::Value,
+    context: &'a PluginContext,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PluginOutput {
+    Program { program: Program },
+    Error { error: String },
+}
+
+static STORE: Lazy<Store> = Lazy::new(Store::default);
+
+/// Compiled plugins, shared by every file, with the modification time of the
+/// file they were compiled from.
+static MODULES: Lazy<Mutex<HashMap<PathBuf, (SystemTime, WasmModule)>>> =
+    Lazy::new(Default::default);
+
+pub fn plugins(
+    plugins: &[PluginConfig],
+    file_name: FileName,
+    is_server: bool,
+    is_development: bool,
+) -> impl Fold + '_ {
+    PluginHost {
+        plugins,
+        context: PluginContext {
+            filename: file_name.to_string(),
+            is_server,
+            is_development,
+        },
+    }
+}
+
+struct PluginHost<'a> {
+    plugins: &'a [PluginConfig],
+    context: PluginContext,
+}
+
+impl PluginHost<'_> {
+    fn run(&self, mut program: Program) -> Program {
+        for plugin in self.plugins {
+            match self.run_plugin(plugin, &program) {
+                Ok(transformed) => program = transformed,
+                Err(err) => emit_error(
+                    program.span().shrink_to_lo(),
+                    PLUGIN_FAILED,
+                    &format!(
+                        "Plugin `{}` ({}) failed: {:#}",
+                        plugin.name(),
+                        plugin.path.display(),
+                        err
+                    ),
+                ),
+            }
+        }
+
+        program
+    }
+
+    fn run_plugin(&self, plugin: &PluginConfig, program: &Program) -> Result<Program, Error> {
+        let input = serde_json::to_vec(&PluginInput {
+            program,
+            config: &plugin.config,
+            context: &self.context,
+        })?;
+
+        let module = load_module(&plugin.path)?;
+        let instance = Instance::new(&module, &imports! {})?;
+        let memory = instance.exports.get_memory("memory")?;
+        let alloc = instance.exports.get_native_function::<i32, i32>("alloc")?;
+        let transform = instance
+            .exports
+            .get_native_function::<(i32, i32), i64>("transform")?;
+
+        let len = i32::try_from(input.len()).context("the program is too large")?;
+        let ptr = alloc.call(len)?;
+        write_memory(memory, ptr as u32 as usize, &input)?;
+
+        let ret = transform.call(ptr, len)?;
+        let output = read_memory(memory, (ret >> 32) as u32 as usize, ret as u32 as usize)?;
+
+        let transformed =
+            match serde_json::from_slice(&output).context("failed to deserialize the output")? {
+                PluginOutput::Program { program } => program,
+                PluginOutput::Error { error } => bail!(error),
+            };
+
+        match (program, &transformed) {
+            (Program::Module(..), Program::Module(..))
+            | (Program::Script(..), Program::Script(..)) => Ok(transformed),
+            _ => bail!("the plugin changed the type of the program"),
+        }
+    }
+}
+
+impl Fold for PluginHost<'_> {
+    fn fold_program(&mut self, program: Program) -> Program {
+        self.run(program)
+    }
+
+    fn fold_module(&mut self, module: Module) -> Module {
+        match self.run(Program::Module(module)) {
+            Program::Module(module) => module,
+            Program::Script(..) => unreachable!("plugins should not change the program type"),
+        }
+    }
+
+    fn fold_script(&mut self, script: Script) -> Script {
+        match self.run(Program::Script(script)) {
+            Program::Script(script) => script,
+            Program::Module(..) => unreachable!("plugins should not change the program type"),
+        }
+    }
+}
+
+/// Returns the compiled module of `path`, which is compiled again if the file
+/// changed since it was cached.
+fn load_module(path: &Path) -> Result<WasmModule, Error> {
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .with_context(|| format!("failed to read {}", path.display()))?;
+
+    let mut modules = MODULES.lock().unwrap();
+    if let Some((cached_modified, module)) = modules.get(path) {
+        if *cached_modified == modified {
+            return Ok(module.clone());
+        }
+    }
+
+    let module = WasmModule::from_file(&*STORE, path)
+        .with_context(|| format!("failed to compile {}", path.display()))?;
+    modules.insert(path.to_path_buf(), (modified, module.clone()));
+
+    Ok(module)
+}
+
+fn write_memory(memory: &Memory, ptr: usize, data: &[u8]) -> Result<(), Error> {
+    let view = memory.view::<u8>();
+    let cells = match view.get(ptr..ptr + data.len()) {
+        Some(cells) => cells,
+        None => bail!("`alloc` returned a pointer out of bounds"),
+    };
+    for (cell, &byte) in cells.iter().zip(data) {
+        cell.set(byte);
+    }
+
+    Ok(())
+}
+
+fn read_memory(memory: &Memory, ptr: usize, len: usize) -> Result<Vec<u8>, Error> {
+    let view = memory.view::<u8>();
+    match view.get(ptr..ptr + len) {
+        Some(cells) => Ok(cells.iter().map(|cell| cell.get()).collect()),
+        None => bail!("`transform` returned a pointer out of bounds"),
+    }
+}
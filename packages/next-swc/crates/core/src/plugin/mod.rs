@@ -0,0 +1,52 @@
+//! Runs custom transforms compiled to WebAssembly.
+//!
+//! A plugin module exports its `memory` and two functions:
+//!
+//! - `alloc(len: i32) -> i32` returns a pointer to `len` bytes the host writes
+//!   the input to.
+//! - `transform(ptr: i32, len: i32) -> i64` reads the input and returns the
+//!   pointer to its output in the upper 32 bits and the length in the lower 32
+//!   bits.
+//!
+//! The input is the JSON of `{ program, config, context }`, where `program` is
+//! the swc AST. The output is the JSON of `{ program }` or `{ error }`. Plugins
+//! are instantiated once per file and cannot import anything.
+//!
+//! The host is only built with the `plugin` feature, because wasmer does not
+//! support every target next-swc is built for. Without it, each configured
+//! plugin is reported as an error.
+
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+#[cfg(feature = "plugin")]
+mod host;
+#[cfg(not(feature = "plugin"))]
+mod unsupported;
+
+#[cfg(feature = "plugin")]
+pub use host::plugins;
+#[cfg(not(feature = "plugin"))]
+pub use unsupported::plugins;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginConfig {
+    /// Path to the `.wasm` file.
+    pub path: PathBuf,
+
+    /// Passed to the plugin as is.
+    #[serde(default)]
+    pub config: serde_json::Value,
+}
+
+impl PluginConfig {
+    /// Name of the plugin in diagnostics, which is the name of its file.
+    pub fn name(&self) -> Cow<'_, str> {
+        match self.path.file_stem() {
+            Some(stem) => stem.to_string_lossy(),
+            None => self.path.to_string_lossy(),
+        }
+    }
+}
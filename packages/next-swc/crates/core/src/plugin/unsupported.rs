@@ -0,0 +1,49 @@
+use swc_common::{FileName, Span};
+use swc_ecmascript::ast::{Module, Script};
+use swc_ecmascript::visit::Fold;
+
+use super::PluginConfig;
+use crate::diagnostics::{emit_error, PLUGIN_FAILED};
+
+/// Reports every plugin as failed, as this build of next-swc cannot run them.
+pub fn plugins(
+    plugins: &[PluginConfig],
+    _file_name: FileName,
+    _is_server: bool,
+    _is_development: bool,
+) -> impl Fold + '_ {
+    Unsupported { plugins }
+}
+
+struct Unsupported<'a> {
+    plugins: &'a [PluginConfig],
+}
+
+impl Unsupported<'_> {
+    fn report(&self, span: Span) {
+        for plugin in self.plugins {
+            emit_error(
+                span.shrink_to_lo(),
+                PLUGIN_FAILED,
+                &format!(
+                    "Plugin `{}` ({}) failed: Wasm plugins are not supported by this build of \
+                     next-swc",
+                    plugin.name(),
+                    plugin.path.display()
+                ),
+            );
+        }
+    }
+}
+
+impl Fold for Unsupported<'_> {
+    fn fold_module(&mut self, module: Module) -> Module {
+        self.report(module.span);
+        module
+    }
+
+    fn fold_script(&mut self, script: Script) -> Script {
+        self.report(script.span);
+        script
+    }
+}
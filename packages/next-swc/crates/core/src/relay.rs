@@ -3,12 +3,14 @@ use regex::Regex;
 use serde::Deserialize;
 use std::path::{Path, PathBuf};
 use swc_atoms::JsWord;
-use swc_common::errors::HANDLER;
 use swc_common::FileName;
 use swc_ecmascript::ast::*;
 use swc_ecmascript::utils::{quote_ident, ExprFactory};
 use swc_ecmascript::visit::{Fold, FoldWith};
 
+use crate::diagnostics::{emit_error, RELAY_ARTIFACT};
+use crate::metadata::SharedMetadata;
+
 #[derive(Copy, Clone, Debug, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum RelayLanguageConfig {
@@ -27,6 +29,7 @@ struct Relay<'a> {
     pages_dir: PathBuf,
     file_name: FileName,
     config: &'a Config,
+    metadata: SharedMetadata,
 }
 
 #[derive(Deserialize, Debug, Default, Clone)]
@@ -139,7 +142,10 @@ impl<'a> Relay<'a> {
         match operation_name {
             None => None,
             Some(operation_name) => match self.build_require_path(operation_name.as_str()) {
-                Ok(final_path) => Some(build_require_expr_from_path(final_path.to_str().unwrap())),
+                Ok(final_path) => {
+                    self.metadata.borrow_mut().relay = true;
+                    Some(build_require_expr_from_path(final_path.to_str().unwrap()))
+                }
                 Err(err) => {
                     let base_error = "Could not transform GraphQL template to a Relay import.";
                     let error_message = match err {
@@ -158,12 +164,11 @@ impl<'a> Relay<'a> {
                         }
                     };
 
-                    HANDLER.with(|handler| {
-                        handler.span_err(
-                            tpl.span,
-                            format!("{} {}", base_error, error_message).as_str(),
-                        );
-                    });
+                    emit_error(
+                        tpl.span,
+                        RELAY_ARTIFACT,
+                        format!("{} {}", base_error, error_message).as_str(),
+                    );
 
                     None
                 }
@@ -176,11 +181,13 @@ pub fn relay<'a>(
     config: &'a Config,
     file_name: FileName,
     pages_dir: Option<PathBuf>,
+    metadata: SharedMetadata,
 ) -> impl Fold + '_ {
     Relay {
         root_dir: std::env::current_dir().unwrap(),
         file_name,
         pages_dir: pages_dir.unwrap_or_else(|| panic!("pages_dir is expected.")),
         config,
+        metadata,
     }
 }
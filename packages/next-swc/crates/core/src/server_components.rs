@@ -0,0 +1,306 @@
+use serde::Serialize;
+use swc_atoms::js_word;
+use swc_common::{FileName, DUMMY_SP};
+use swc_ecmascript::ast::*;
+use swc_ecmascript::utils::{find_ids, private_ident, quote_ident, ExprFactory};
+use swc_ecmascript::visit::{noop_fold_type, Fold};
+
+use crate::diagnostics::{emit_error, EXPORT_ALL_IN_CLIENT_MODULE, MISPLACED_DIRECTIVE};
+use crate::metadata::SharedMetadata;
+use crate::shake_exports::module_export_name;
+
+/// A module-level `"use client"` or `"use server"` directive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Directive {
+    Client,
+    Server,
+}
+
+impl Directive {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "use client" => Some(Directive::Client),
+            "use server" => Some(Directive::Server),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Directive::Client => "use client",
+            Directive::Server => "use server",
+        }
+    }
+}
+
+/// Reports the `"use client"` or `"use server"` directive of a module in the
+/// metadata, and replaces client modules with references to them on the
+/// server.
+pub fn server_components(
+    file_name: FileName,
+    is_server: bool,
+    metadata: SharedMetadata,
+) -> impl Fold {
+    ServerComponents {
+        file_name,
+        is_server,
+        metadata,
+    }
+}
+
+struct ServerComponents {
+    file_name: FileName,
+    is_server: bool,
+    metadata: SharedMetadata,
+}
+
+/// Returns the directive of `module`, or `Err` if a directive is misplaced.
+fn find_directive(module: &Module) -> Result<Option<Directive>, ()> {
+    let mut directive = None;
+    let mut in_prologue = true;
+    let mut valid = true;
+
+    for item in &module.body {
+        let (value, span) = match item {
+            ModuleItem::Stmt(Stmt::Expr(ExprStmt { expr, span })) => match &**expr {
+                Expr::Lit(Lit::Str(s)) => (&s.value, *span),
+                _ => {
+                    in_prologue = false;
+                    continue;
+                }
+            },
+            _ => {
+                in_prologue = false;
+                continue;
+            }
+        };
+        // Other string literals, like `'use strict'`, are part of the prologue too.
+        let found = match Directive::parse(value) {
+            Some(found) => found,
+            None => continue,
+        };
+
+        if !in_prologue {
+            emit_error(
+                span,
+                MISPLACED_DIRECTIVE,
+                &format!(
+                    "The \"{}\" directive must be placed before other statements and imports.",
+                    found.as_str()
+                ),
+            );
+            valid = false;
+            continue;
+        }
+
+        match directive {
+            Some(prev) if prev != found => {
+                emit_error(
+                    span,
+                    MISPLACED_DIRECTIVE,
+                    "\"use client\" and \"use server\" cannot be used in the same file.",
+                );
+                valid = false;
+            }
+            _ => directive = Some(found),
+        }
+    }
+
+    if valid {
+        Ok(directive)
+    } else {
+        Err(())
+    }
+}
+
+/// Returns the names exported by `module`, or `None` if they are not known.
+fn export_names(module: &Module) -> Option<Vec<ModuleExportName>> {
+    let mut names = vec![];
+    let mut known = true;
+
+    for item in &module.body {
+        let decl = match item {
+            ModuleItem::ModuleDecl(decl) => decl,
+            ModuleItem::Stmt(..) => continue,
+        };
+
+        match decl {
+            ModuleDecl::ExportDecl(ExportDecl { decl, .. }) => match decl {
+                Decl::Fn(f) => names.push(ModuleExportName::Ident(f.ident.clone())),
+                Decl::Class(c) => names.push(ModuleExportName::Ident(c.ident.clone())),
+                Decl::Var(v) => {
+                    let ids: Vec<Ident> = find_ids(&v.decls);
+                    names.extend(ids.into_iter().map(ModuleExportName::Ident));
+                }
+                _ => {}
+            },
+            ModuleDecl::ExportNamed(export) => {
+                for specifier in &export.specifiers {
+                    match specifier {
+                        ExportSpecifier::Named(named) => {
+                            names.push(named.exported.as_ref().unwrap_or(&named.orig).clone());
+                        }
+                        ExportSpecifier::Namespace(namespace) => names.push(namespace.name.clone()),
+                        _ => {}
+                    }
+                }
+            }
+            ModuleDecl::ExportDefaultDecl(..) | ModuleDecl::ExportDefaultExpr(..) => {
+                names.push(ModuleExportName::Ident(quote_ident!("default")))
+            }
+            ModuleDecl::ExportAll(export) => {
+                emit_error(
+                    export.span,
+                    EXPORT_ALL_IN_CLIENT_MODULE,
+                    "`export * from '...'` cannot be used in a \"use client\" module, because the \
+                     exports of the module must be known.",
+                );
+                known = false;
+            }
+            _ => {}
+        }
+    }
+
+    if known {
+        Some(names)
+    } else {
+        None
+    }
+}
+
+fn str_lit(value: &str) -> Expr {
+    Expr::Lit(Lit::Str(Str {
+        span: DUMMY_SP,
+        value: value.into(),
+        has_escape: false,
+        kind: Default::default(),
+    }))
+}
+
+fn key_value(key: &str, value: Expr) -> PropOrSpread {
+    PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+        key: PropName::Ident(Ident::new(key.into(), DUMMY_SP)),
+        value: Box::new(value),
+    })))
+}
+
+/// `const id = init`
+fn const_decl(id: Ident, init: Box<Expr>) -> VarDecl {
+    VarDecl {
+        span: DUMMY_SP,
+        kind: VarDeclKind::Const,
+        declare: false,
+        decls: vec![VarDeclarator {
+            span: DUMMY_SP,
+            name: Pat::Ident(BindingIdent { id, type_ann: None }),
+            init: Some(init),
+            definite: false,
+        }],
+    }
+}
+
+impl ServerComponents {
+    /// `{ $$typeof: Symbol.for('react.module.reference'), filepath, name }`
+    fn client_reference(&self, name: &str) -> Expr {
+        let module_reference = Expr::Call(CallExpr {
+            span: DUMMY_SP,
+            callee: MemberExpr {
+                span: DUMMY_SP,
+                obj: Box::new(Expr::Ident(quote_ident!("Symbol"))),
+                prop: MemberProp::Ident(quote_ident!("for")),
+            }
+            .as_callee(),
+            args: vec![str_lit("react.module.reference").as_arg()],
+            type_args: None,
+        });
+
+        Expr::Object(ObjectLit {
+            span: DUMMY_SP,
+            props: vec![
+                key_value("$$typeof", module_reference),
+                key_value("filepath", str_lit(&self.file_name.to_string())),
+                key_value("name", str_lit(name)),
+            ],
+        })
+    }
+
+    /// Replaces the body of a client module with a reference for each export.
+    fn client_reference_stub(&self, module: Module) -> Module {
+        let names = match export_names(&module) {
+            Some(names) => names,
+            None => return module,
+        };
+
+        let body = names
+            .into_iter()
+            .flat_map(|name| {
+                let reference = Box::new(self.client_reference(module_export_name(&name)));
+                if *module_export_name(&name) == js_word!("default") {
+                    return vec![ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(
+                        ExportDefaultExpr {
+                            span: DUMMY_SP,
+                            expr: reference,
+                        },
+                    ))];
+                }
+
+                match name {
+                    ModuleExportName::Ident(ident) => {
+                        let id = Ident::new(ident.sym, DUMMY_SP);
+                        vec![ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+                            span: DUMMY_SP,
+                            decl: Decl::Var(const_decl(id, reference)),
+                        }))]
+                    }
+                    // A string name like `"a-b"` cannot be declared, so bind the reference
+                    // locally and export it under that name.
+                    ModuleExportName::Str(..) => {
+                        let local = private_ident!("_reference");
+                        vec![
+                            ModuleItem::Stmt(Stmt::Decl(Decl::Var(const_decl(
+                                local.clone(),
+                                reference,
+                            )))),
+                            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport {
+                                span: DUMMY_SP,
+                                specifiers: vec![ExportSpecifier::Named(ExportNamedSpecifier {
+                                    span: DUMMY_SP,
+                                    orig: ModuleExportName::Ident(local),
+                                    exported: Some(name),
+                                    is_type_only: false,
+                                })],
+                                src: None,
+                                type_only: false,
+                                asserts: None,
+                            })),
+                        ]
+                    }
+                }
+            })
+            .collect();
+
+        Module {
+            span: module.span,
+            body,
+            shebang: None,
+        }
+    }
+}
+
+impl Fold for ServerComponents {
+    noop_fold_type!();
+
+    fn fold_module(&mut self, module: Module) -> Module {
+        let directive = match find_directive(&module) {
+            Ok(directive) => directive,
+            Err(()) => return module,
+        };
+        self.metadata.borrow_mut().directive = directive;
+
+        match directive {
+            Some(Directive::Client) if self.is_server => self.client_reference_stub(module),
+            _ => module,
+        }
+    }
+}
@@ -17,6 +17,22 @@ pub fn shake_exports(config: Config) -> impl Fold {
     }
 }
 
+/// Name under which `spec` is exported, like `b` for `export { a as b }`.
+pub(crate) fn exported_name(spec: &ExportNamedSpecifier) -> Option<&JsWord> {
+    match spec.exported.as_ref().unwrap_or(&spec.orig) {
+        ModuleExportName::Ident(ident) => Some(&ident.sym),
+        _ => None,
+    }
+}
+
+/// Value of `name`, like `a-b` for `export { a as "a-b" }`.
+pub(crate) fn module_export_name(name: &ModuleExportName) -> &JsWord {
+    match name {
+        ModuleExportName::Ident(ident) => &ident.sym,
+        ModuleExportName::Str(s) => &s.value,
+    }
+}
+
 #[derive(Debug, Default)]
 struct ExportShaker {
     ignore: Vec<JsWord>,
@@ -79,21 +95,14 @@ impl Fold for ExportShaker {
         export.specifiers = export
             .specifiers
             .into_iter()
-            .filter_map(|spec| {
-                if let ExportSpecifier::Named(named_spec) = spec {
-                    if let Some(ident) = &named_spec.exported {
-                        if let ModuleExportName::Ident(ident) = ident {
-                            if self.ignore.contains(&ident.sym) {
-                                return Some(ExportSpecifier::Named(named_spec));
-                            }
-                        }
-                    } else if let ModuleExportName::Ident(ident) = &named_spec.orig {
-                        if self.ignore.contains(&ident.sym) {
-                            return Some(ExportSpecifier::Named(named_spec));
-                        }
+            .filter_map(|spec| match spec {
+                ExportSpecifier::Named(named_spec) => match exported_name(&named_spec) {
+                    Some(name) if self.ignore.contains(name) => {
+                        Some(ExportSpecifier::Named(named_spec))
                     }
-                }
-                None
+                    _ => None,
+                },
+                _ => None,
             })
             .collect();
         if export.specifiers.is_empty() {
@@ -18,13 +18,14 @@ use swc_ecmascript::utils::{drop_span, private_ident};
 use swc_ecmascript::visit::{Fold, FoldWith};
 
 //use external::external_styles;
+use crate::metadata::SharedMetadata;
 use transform_css::transform_css;
 use utils::*;
 
 mod transform_css;
 mod utils;
 
-pub fn styled_jsx(cm: Arc<SourceMap>, file_name: FileName) -> impl Fold {
+pub fn styled_jsx(cm: Arc<SourceMap>, file_name: FileName, metadata: SharedMetadata) -> impl Fold {
     let file_name = match file_name {
         FileName::Real(real_file_name) => real_file_name
             .to_str()
@@ -51,6 +52,7 @@ pub fn styled_jsx(cm: Arc<SourceMap>, file_name: FileName) -> impl Fold {
         add_default_decl: Default::default(),
         in_function_params: Default::default(),
         evaluator: Default::default(),
+        metadata,
     }
 }
 
@@ -73,6 +75,7 @@ struct StyledJSXTransformer {
     add_default_decl: Option<(Id, Expr)>,
     in_function_params: bool,
     evaluator: Option<Evaluator>,
+    metadata: SharedMetadata,
 }
 
 pub struct LocalStyle {
@@ -334,6 +337,7 @@ impl Fold for StyledJSXTransformer {
         }
 
         if self.file_has_styled_jsx || self.file_has_css_resolve {
+            self.metadata.borrow_mut().styled_jsx = true;
             prepend(
                 &mut new_items,
                 styled_jsx_import_decl(self.style_import_name.as_ref().unwrap()),
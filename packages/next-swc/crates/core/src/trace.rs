@@ -0,0 +1,241 @@
+//! Timing spans written in the format of the Next.js build trace, so they can
+//! be sent to Jaeger with `scripts/send-trace-to-jaeger`.
+//!
+//! Spans are only recorded while a [Tracer] is installed with [with_tracer].
+
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use swc_ecmascript::ast::{Module, Program, Script};
+use swc_ecmascript::visit::Fold;
+
+/// Ids of the spans recorded by next-swc. They start far above the ids of
+/// the spans of the JS build, which count from 1.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1 << 32);
+
+thread_local! {
+    static TRACER: RefCell<Option<Tracer>> = RefCell::new(None);
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceConfig {
+    /// File the spans are appended to, like `.next/trace`.
+    pub file: PathBuf,
+
+    pub trace_id: String,
+
+    /// Span of the JS build the spans belong to.
+    #[serde(default)]
+    pub parent_id: Option<u64>,
+
+    /// `process.hrtime.bigint() / 1000n` when the transform was requested.
+    pub start_time: u64,
+}
+
+/// A span of the Next.js trace. Times are in microseconds.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TraceEvent {
+    trace_id: String,
+    parent_id: Option<u64>,
+    name: String,
+    id: u64,
+    timestamp: u64,
+    duration: u64,
+    tags: HashMap<String, String>,
+}
+
+struct OpenSpan {
+    id: u64,
+    name: String,
+    start: Instant,
+    tags: HashMap<String, String>,
+}
+
+pub struct Tracer {
+    config: TraceConfig,
+    /// The instant of [TraceConfig::start_time].
+    started: Instant,
+    open: Vec<OpenSpan>,
+    events: Vec<TraceEvent>,
+}
+
+impl Tracer {
+    /// `started` should be taken when `config.start_time` was, on the JS
+    /// thread.
+    pub fn new(config: TraceConfig, started: Instant) -> Self {
+        Tracer {
+            config,
+            started,
+            open: vec![],
+            events: vec![],
+        }
+    }
+
+    fn begin(&mut self, name: String, tags: HashMap<String, String>) -> u64 {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        self.open.push(OpenSpan {
+            id,
+            name,
+            start: Instant::now(),
+            tags,
+        });
+
+        id
+    }
+
+    /// Ends the span `id` and the spans started inside it which are still
+    /// open.
+    fn end(&mut self, id: u64) {
+        if !self.open.iter().any(|span| span.id == id) {
+            return;
+        }
+        while let Some(span) = self.open.pop() {
+            let ended = span.id == id;
+            self.record(span);
+            if ended {
+                break;
+            }
+        }
+    }
+
+    /// Records `span`, which was just removed from [Tracer::open].
+    fn record(&mut self, span: OpenSpan) {
+        let parent_id = match self.open.last() {
+            Some(parent) => Some(parent.id),
+            None => self.config.parent_id,
+        };
+
+        self.events.push(TraceEvent {
+            trace_id: self.config.trace_id.clone(),
+            parent_id,
+            name: span.name,
+            id: span.id,
+            timestamp: self.config.start_time
+                + span
+                    .start
+                    .saturating_duration_since(self.started)
+                    .as_micros() as u64,
+            duration: span.start.elapsed().as_micros() as u64,
+            tags: span.tags,
+        });
+    }
+
+    /// Ends the spans which are still open and appends every span to the trace
+    /// file as a single line.
+    pub fn finish(mut self) -> io::Result<()> {
+        while let Some(span) = self.open.pop() {
+            self.record(span);
+        }
+        if self.events.is_empty() {
+            return Ok(());
+        }
+
+        let mut line = serde_json::to_vec(&self.events)?;
+        line.push(b'\n');
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.config.file)?
+            .write_all(&line)
+    }
+}
+
+/// Installs `tracer` while running `op`, and returns it with the spans
+/// recorded by `op`.
+pub fn with_tracer<F, Ret>(tracer: Tracer, op: F) -> (Ret, Tracer)
+where
+    F: FnOnce() -> Ret,
+{
+    let prev = TRACER.with(|t| t.borrow_mut().replace(tracer));
+    let ret = op();
+    let tracer = TRACER.with(|t| std::mem::replace(&mut *t.borrow_mut(), prev));
+
+    (
+        ret,
+        tracer.expect("the tracer should not be removed by `op`"),
+    )
+}
+
+/// Ends its span when it's dropped, so spans are closed in order even if the
+/// code they time returns early or panics.
+#[must_use = "the span ends when the guard is dropped"]
+pub struct SpanGuard {
+    /// `None` if no tracer was installed when the span started.
+    id: Option<u64>,
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            TRACER.with(|t| {
+                if let Some(tracer) = &mut *t.borrow_mut() {
+                    tracer.end(id);
+                }
+            });
+        }
+    }
+}
+
+/// Starts a span which is a child of the innermost open span. The span ends,
+/// along with the spans started inside it, when the guard is dropped.
+pub fn begin(name: &str) -> SpanGuard {
+    begin_with_tags(name, HashMap::new())
+}
+
+pub fn begin_with_tags(name: &str, tags: HashMap<String, String>) -> SpanGuard {
+    let id = TRACER.with(|t| {
+        t.borrow_mut()
+            .as_mut()
+            .map(|tracer| tracer.begin(name.to_string(), tags))
+    });
+
+    SpanGuard { id }
+}
+
+/// Runs `op` in a span named `name`.
+pub fn span<F, Ret>(name: &str, op: F) -> Ret
+where
+    F: FnOnce() -> Ret,
+{
+    let _span = begin(name);
+    op()
+}
+
+/// A pass which only calls `f`. It's used to mark the boundaries of the passes
+/// run by swc.
+pub fn hook<F>(f: F) -> impl Fold
+where
+    F: FnMut(),
+{
+    Hook(f)
+}
+
+struct Hook<F>(F);
+
+impl<F> Fold for Hook<F>
+where
+    F: FnMut(),
+{
+    fn fold_program(&mut self, program: Program) -> Program {
+        (self.0)();
+        program
+    }
+
+    fn fold_module(&mut self, module: Module) -> Module {
+        (self.0)();
+        module
+    }
+
+    fn fold_script(&mut self, script: Script) -> Script {
+        (self.0)();
+        script
+    }
+}
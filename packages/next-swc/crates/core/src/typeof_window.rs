@@ -0,0 +1,151 @@
+use fxhash::FxHashSet;
+use serde::Deserialize;
+use swc_common::SyntaxContext;
+use swc_ecmascript::ast::*;
+use swc_ecmascript::utils::ident::IdentLike;
+use swc_ecmascript::visit::{noop_fold_type, noop_visit_type, Fold, FoldWith, Visit, VisitWith};
+
+use crate::const_fold::const_fold;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    /// Also replace `typeof document`.
+    #[serde(default)]
+    pub document: bool,
+}
+
+/// Replaces `typeof window` with `"undefined"` on the server and `"object"` in
+/// the browser, and removes the branches which are dead for the target.
+///
+/// Imports which were only used by the removed branches are removed too, so
+/// server-only modules don't end up in the client bundle. The rest of the file
+/// is left as is.
+pub fn typeof_window(config: Config, is_server: bool) -> impl Fold {
+    TypeofWindow {
+        config,
+        is_server,
+        replaced: false,
+    }
+}
+
+struct TypeofWindow {
+    config: Config,
+    is_server: bool,
+    replaced: bool,
+}
+
+impl TypeofWindow {
+    fn is_replaced_global(&self, e: &Expr) -> bool {
+        match e {
+            Expr::Ident(i) if i.span.ctxt == SyntaxContext::empty() => {
+                &*i.sym == "window" || (self.config.document && &*i.sym == "document")
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Fold for TypeofWindow {
+    noop_fold_type!();
+
+    fn fold_module(&mut self, module: Module) -> Module {
+        let module = module.fold_children_with(self);
+        if !self.replaced {
+            return module;
+        }
+
+        let used_before = references(&module);
+        let mut module = module.fold_with(&mut const_fold());
+        let used_after = references(&module);
+
+        // Imports which were only used by the removed branches.
+        let is_unused = |specifier: &ImportSpecifier| {
+            let id = import_local(specifier).to_id();
+            used_before.contains(&id) && !used_after.contains(&id)
+        };
+        module.body = module
+            .body
+            .into_iter()
+            .filter_map(|item| match item {
+                ModuleItem::ModuleDecl(ModuleDecl::Import(mut import))
+                    if !import.specifiers.is_empty() =>
+                {
+                    import.specifiers.retain(|specifier| !is_unused(specifier));
+                    if import.specifiers.is_empty() {
+                        None
+                    } else {
+                        Some(ModuleItem::ModuleDecl(ModuleDecl::Import(import)))
+                    }
+                }
+                item => Some(item),
+            })
+            .collect();
+
+        module
+    }
+
+    fn fold_script(&mut self, script: Script) -> Script {
+        let script = script.fold_children_with(self);
+        if !self.replaced {
+            return script;
+        }
+
+        script.fold_with(&mut const_fold())
+    }
+
+    fn fold_expr(&mut self, e: Expr) -> Expr {
+        match e {
+            Expr::Unary(UnaryExpr {
+                op: UnaryOp::TypeOf,
+                arg,
+                span,
+            }) if self.is_replaced_global(&arg) => {
+                self.replaced = true;
+
+                let value = if self.is_server {
+                    "undefined"
+                } else {
+                    "object"
+                };
+                Expr::Lit(Lit::Str(Str {
+                    span,
+                    value: value.into(),
+                    has_escape: false,
+                    kind: Default::default(),
+                }))
+            }
+            _ => e.fold_children_with(self),
+        }
+    }
+}
+
+fn import_local(specifier: &ImportSpecifier) -> &Ident {
+    match specifier {
+        ImportSpecifier::Named(ImportNamedSpecifier { local, .. })
+        | ImportSpecifier::Default(ImportDefaultSpecifier { local, .. })
+        | ImportSpecifier::Namespace(ImportStarAsSpecifier { local, .. }) => local,
+    }
+}
+
+/// Returns the identifiers used by `module` outside of its imports.
+fn references(module: &Module) -> FxHashSet<Id> {
+    let mut finder = ReferenceFinder::default();
+    module.visit_with(&mut finder);
+    finder.refs
+}
+
+#[derive(Default)]
+struct ReferenceFinder {
+    refs: FxHashSet<Id>,
+}
+
+impl Visit for ReferenceFinder {
+    noop_visit_type!();
+
+    fn visit_import_decl(&mut self, _: &ImportDecl) {}
+
+    fn visit_ident(&mut self, i: &Ident) {
+        self.refs.insert(i.to_id());
+    }
+}
@@ -0,0 +1,14 @@
+import Head from 'next/head'
+
+export const config = { amp: true }
+
+export default function About() {
+  return (
+    <>
+      <Head>
+        <title>About</title>
+      </Head>
+      <amp-img src="/team.png" width="300" height="300" />
+    </>
+  )
+}
@@ -0,0 +1,19 @@
+import dynamic from 'next/dynamic'
+import fs from 'fs'
+
+const Chart = dynamic(() => import('../components/chart'))
+
+export const config = { amp: 'hybrid' }
+
+export async function getStaticProps() {
+  return { props: { data: fs.readFileSync('data.json', 'utf8') } }
+}
+
+export default function Page({ data }) {
+  return (
+    <div>
+      {data}
+      <Chart />
+    </div>
+  )
+}
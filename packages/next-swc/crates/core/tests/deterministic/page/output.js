@@ -0,0 +1,13 @@
+import dynamic from "next/dynamic";
+const Chart = dynamic(() => import("../components/chart"), {
+    loadableGenerated: {
+        webpack: () => [require.resolveWeak("../components/chart")]
+    }
+});
+export var __N_SSG = true;
+export const config = {
+    amp: "hybrid"
+};
+export default function Page({ data }) {
+    return React.createElement("div", null, data, React.createElement(Chart, null));
+}
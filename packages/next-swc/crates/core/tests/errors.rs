@@ -1,11 +1,25 @@
 use next_swc::{
-    disallow_re_export_all_in_page::disallow_re_export_all_in_page, next_dynamic::next_dynamic,
-    next_ssg::next_ssg, styled_jsx::styled_jsx,
+    custom_before_pass,
+    disallow_re_export_all_in_page::disallow_re_export_all_in_page,
+    edge_runtime::edge_runtime,
+    environment_imports::environment_imports,
+    metadata::SharedMetadata,
+    modularize_imports::modularize_imports,
+    next_dynamic::next_dynamic,
+    next_ssg::next_ssg,
+    page_config::{page_config, page_config_test},
+    page_exports::page_exports,
+    server_components::server_components,
+    styled_jsx::styled_jsx,
+    TransformOptions,
 };
-use std::path::PathBuf;
-use swc_common::FileName;
+use std::path::{Path, PathBuf};
+use swc_common::{chain, FileName};
 use swc_ecma_transforms_testing::test_fixture_allowing_error;
-use swc_ecmascript::parser::{EsConfig, Syntax};
+use swc_ecmascript::{
+    parser::{EsConfig, Syntax},
+    transforms::resolver,
+};
 use testing::fixture;
 
 fn syntax() -> Syntax {
@@ -26,6 +40,35 @@ fn re_export_all_in_page(input: PathBuf) {
     );
 }
 
+#[fixture("tests/errors/page-exports/**/input.js")]
+fn page_exports_errors(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    // `page-exports/api/component/input.js` is checked as `pages/api/component.js`.
+    let page: PathBuf = input
+        .parent()
+        .unwrap()
+        .components()
+        .skip_while(|c| c.as_os_str() != "page-exports")
+        .skip(1)
+        .collect();
+    let pages_dir = Path::new("/some-project/pages");
+    let file_name = FileName::Real(pages_dir.join(page).with_extension("js"));
+    test_fixture_allowing_error(
+        syntax(),
+        &|_tr| page_exports(&file_name, Some(pages_dir), true),
+        &input,
+        &output,
+    );
+    // Without `pagesDir`, the file is classified by the `pages` directory in its
+    // path.
+    test_fixture_allowing_error(
+        syntax(),
+        &|_tr| page_exports(&file_name, None, true),
+        &input,
+        &output,
+    );
+}
+
 #[fixture("tests/errors/next-dynamic/**/input.js")]
 fn next_dynamic_errors(input: PathBuf) {
     let output = input.parent().unwrap().join("output.js");
@@ -37,6 +80,7 @@ fn next_dynamic_errors(input: PathBuf) {
                 false,
                 FileName::Real(PathBuf::from("/some-project/src/some-file.js")),
                 Some("/some-project/src".into()),
+                Default::default(),
             )
         },
         &input,
@@ -54,7 +98,7 @@ fn styled_jsx_errors(input: PathBuf) {
 
     test_fixture_allowing_error(
         syntax(),
-        &|t| styled_jsx(t.cm.clone(), file_name.clone()),
+        &|t| styled_jsx(t.cm.clone(), file_name.clone(), Default::default()),
         &input,
         &output,
     );
@@ -63,5 +107,156 @@ fn styled_jsx_errors(input: PathBuf) {
 #[fixture("tests/errors/next-ssg/**/input.js")]
 fn next_ssg_errors(input: PathBuf) {
     let output = input.parent().unwrap().join("output.js");
-    test_fixture_allowing_error(syntax(), &|_tr| next_ssg(), &input, &output);
+    test_fixture_allowing_error(
+        syntax(),
+        &|_tr| next_ssg(false, Default::default()),
+        &input,
+        &output,
+    );
+}
+
+#[fixture("tests/errors/next-ssg-retained-imports/**/input.js")]
+fn next_ssg_retained_imports(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    test_fixture_allowing_error(
+        syntax(),
+        &|_tr| next_ssg(true, Default::default()),
+        &input,
+        &output,
+    );
+}
+
+#[cfg(feature = "plugin")]
+#[fixture("tests/errors/plugin/**/input.js")]
+fn plugin_errors(input: PathBuf) {
+    use next_swc::plugin::{plugins, PluginConfig};
+
+    let output = input.parent().unwrap().join("output.js");
+    let config = vec![PluginConfig {
+        path: PathBuf::from("tests/plugins/error.wat"),
+        config: Default::default(),
+    }];
+    test_fixture_allowing_error(
+        syntax(),
+        &|_tr| plugins(&config, FileName::Anon, false, false),
+        &input,
+        &output,
+    );
+}
+
+/// Runs the plugins configured in the options through [custom_before_pass].
+#[cfg(feature = "plugin")]
+#[fixture("tests/errors/plugin/**/input.js")]
+fn plugin_pass_errors(input: PathBuf) {
+    plugin_pass_fixture(&input, "tests/plugins/error.wat");
+}
+
+/// Without the `plugin` feature, configured plugins are reported instead of
+/// being ignored.
+#[cfg(not(feature = "plugin"))]
+#[fixture("tests/errors/plugin-unsupported/**/input.js")]
+fn plugin_unsupported_errors(input: PathBuf) {
+    plugin_pass_fixture(&input, "tests/plugins/identity.wat");
+}
+
+fn plugin_pass_fixture(input: &Path, plugin: &str) {
+    let output = input.parent().unwrap().join("output.js");
+    let options: TransformOptions = serde_json::from_value(serde_json::json!({
+        "plugins": [{ "path": plugin }],
+        "pipeline": { "passes": ["plugins"] },
+    }))
+    .unwrap();
+    test_fixture_allowing_error(
+        syntax(),
+        &|tr| {
+            let file = tr
+                .cm
+                .new_source_file(FileName::Real("input.js".into()), String::new());
+            custom_before_pass(tr.cm.clone(), file, &options, Default::default())
+        },
+        input,
+        &output,
+    );
+}
+
+#[fixture("tests/errors/modularize-imports/**/input.js")]
+fn modularize_imports_errors(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    let config = serde_json::from_value(serde_json::json!({
+        "my-icons": {
+            "transform": "my-icons/{{kebabCase member}}",
+            "preventFullImport": true
+        }
+    }))
+    .unwrap();
+    test_fixture_allowing_error(
+        syntax(),
+        &|_tr| modularize_imports(&config),
+        &input,
+        &output,
+    );
+}
+
+#[fixture("tests/errors/server-components/**/input.js")]
+fn server_components_errors(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    test_fixture_allowing_error(
+        syntax(),
+        &|_tr| {
+            server_components(
+                FileName::Real(PathBuf::from("/some-project/src/some-file.js")),
+                true,
+                Default::default(),
+            )
+        },
+        &input,
+        &output,
+    );
+}
+
+/// The cases in `client` and `server` are compiled for that target.
+#[fixture("tests/errors/environment-imports/**/input.js")]
+fn environment_imports_errors(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    let is_server = input
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .ends_with("server");
+    test_fixture_allowing_error(
+        syntax(),
+        &|_tr| {
+            chain!(
+                resolver(),
+                environment_imports(Default::default(), is_server)
+            )
+        },
+        &input,
+        &output,
+    );
+}
+
+#[fixture("tests/errors/edge-runtime/**/input.js")]
+fn edge_runtime_errors(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    test_fixture_allowing_error(
+        syntax(),
+        &|_tr| {
+            let metadata: SharedMetadata = Default::default();
+            chain!(
+                resolver(),
+                page_config(false, true, "", None, metadata.clone()),
+                edge_runtime(true, metadata)
+            )
+        },
+        &input,
+        &output,
+    );
+}
+
+#[fixture("tests/errors/page-config/**/input.js")]
+fn page_config_errors(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    test_fixture_allowing_error(syntax(), &|_tr| page_config_test(), &input, &output);
 }
@@ -0,0 +1,9 @@
+import fs from 'fs'
+
+export const config = { runtime: 'edge' }
+
+export default function handler(req) {
+  const cwd = process.cwd()
+  const file = require('./file')
+  return eval(new Function(__dirname, req.body.__dirname))
+}
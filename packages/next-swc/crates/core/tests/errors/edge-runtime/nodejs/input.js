@@ -0,0 +1,7 @@
+import fs from 'fs'
+
+export const config = { runtime: 'nodejs' }
+
+export default function handler(req) {
+  return fs.readFileSync(__dirname)
+}
@@ -0,0 +1,5 @@
+import fs from 'fs'
+import { readFile } from 'node:fs/promises'
+import path from './path'
+
+const crypto = require('crypto')
@@ -0,0 +1,4 @@
+import 'server-only'
+import { db } from './db'
+
+export const load = () => import('server-only')
@@ -0,0 +1,2 @@
+import 'client-only'
+import fs from 'fs'
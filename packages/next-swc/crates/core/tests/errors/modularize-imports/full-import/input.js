@@ -0,0 +1,3 @@
+import * as icons from 'my-icons'
+import { ArrowLeft } from 'my-icons'
+import { default as allIcons } from 'my-icons'
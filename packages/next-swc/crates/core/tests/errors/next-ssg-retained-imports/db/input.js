@@ -0,0 +1,11 @@
+import { db } from '../lib/db'
+
+export async function getServerSideProps() {
+  const user = await db.user.findFirst()
+  return { props: { user } }
+}
+
+export default function Page({ user }) {
+  db.track('view')
+  return <div>{user.name}</div>
+}
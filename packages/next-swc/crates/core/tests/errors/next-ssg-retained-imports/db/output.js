@@ -0,0 +1,6 @@
+import { db } from '../lib/db'
+export var __N_SSP = true
+export default function Page({ user }) {
+  db.track('view')
+  return <div>{user.name}</div>
+}
@@ -0,0 +1,17 @@
+export const config = {
+  amp: 'yes',
+  api: {
+    bodyParser: { sizeLimit: 'huge', strict: true },
+    responseLimit: '4 megabytes',
+    timeout: 10,
+  },
+  runtime: 'deno',
+  regions: ['iad1', 1],
+  unstable_runtimeJS: 'false',
+  unstable_foo: true,
+  ...defaults,
+}
+
+export default function Page() {
+  return null
+}
@@ -0,0 +1,5 @@
+export { config } from './config'
+
+export default function Home() {
+  return null
+}
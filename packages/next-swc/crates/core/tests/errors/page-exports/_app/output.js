@@ -0,0 +1,11 @@
+export function getStaticProps() {
+  return { props: {} }
+}
+
+export function reportWebVitals(metric) {
+  console.log(metric)
+}
+
+export default function App({ Component, pageProps }) {
+  return <Component {...pageProps} />
+}
@@ -0,0 +1,5 @@
+import { NextResponse } from 'next/server'
+
+export function middleware(req) {
+  return NextResponse.next()
+}
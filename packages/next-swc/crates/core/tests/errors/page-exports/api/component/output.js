@@ -0,0 +1,3 @@
+export default function Hello() {
+  return <div>Hello</div>
+}
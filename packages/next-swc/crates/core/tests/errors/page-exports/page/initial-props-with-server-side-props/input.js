@@ -0,0 +1,11 @@
+function Page({ user }) {
+  return <div>{user.name}</div>
+}
+
+Page.getInitialProps = async () => ({ user: null })
+
+export async function getServerSideProps() {
+  return { props: { user: { name: 'Ada' } } }
+}
+
+export default Page
@@ -0,0 +1,3 @@
+export function getStaticProps() {
+  return { props: {} }
+}
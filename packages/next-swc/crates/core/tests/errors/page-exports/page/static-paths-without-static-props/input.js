@@ -0,0 +1,7 @@
+export function getStaticPaths() {
+  return { paths: [], fallback: 'blocking' }
+}
+
+export default function Post() {
+  return <article />
+}
@@ -0,0 +1,9 @@
+function formatDate(date) {
+  return date.toISOString()
+}
+
+export { formatDate as 'format-date' }
+
+export default function Page() {
+  return <div />
+}
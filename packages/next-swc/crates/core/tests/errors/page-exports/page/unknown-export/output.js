@@ -0,0 +1,9 @@
+export const revalidate = 10
+
+export function formatDate(date) {
+  return date.toISOString()
+}
+
+export default function Page() {
+  return <div />
+}
@@ -0,0 +1,9 @@
+export const config = { amp: 'hybrid' }
+
+export async function getStaticProps() {
+  return { props: {} }
+}
+
+export default function Home() {
+  return <main />
+}
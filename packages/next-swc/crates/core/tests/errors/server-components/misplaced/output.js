@@ -0,0 +1,6 @@
+import { useState } from 'react'
+'use client'
+
+export default function Counter() {
+  return useState(0)
+}
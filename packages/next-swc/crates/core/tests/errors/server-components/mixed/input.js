@@ -0,0 +1,4 @@
+'use client'
+'use server'
+
+export default function Counter() {}
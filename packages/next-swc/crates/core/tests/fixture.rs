@@ -1,15 +1,21 @@
 use next_swc::{
     amp_attributes::amp_attributes,
+    define::define,
+    metadata::SharedMetadata,
+    modularize_imports::{modularize_imports, Config as ModularizeImportsConfig},
     next_dynamic::next_dynamic,
     next_ssg::next_ssg,
-    page_config::page_config_test,
+    optimize_barrels::{optimize_barrels, Config as OptimizeBarrelsConfig},
+    page_config::{page_config, page_config_test},
     react_remove_properties::remove_properties,
     relay::{relay, Config as RelayConfig, RelayLanguageConfig},
     remove_console::remove_console,
+    server_components::server_components,
     shake_exports::{shake_exports, Config as ShakeExportsConfig},
     styled_jsx::styled_jsx,
+    typeof_window::{typeof_window, Config as TypeofWindowConfig},
 };
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use swc_common::{chain, comments::SingleThreadedComments, FileName, Mark, Span, DUMMY_SP};
 use swc_ecma_transforms_testing::{test, test_fixture};
 use swc_ecmascript::{
@@ -25,6 +31,22 @@ fn syntax() -> Syntax {
     })
 }
 
+/// Compares the fields of `metadata.json` next to `input` with the metadata
+/// collected while transforming it. Fields which are not in the file, or a
+/// missing file, are not checked.
+fn assert_metadata(input: &Path, metadata: &SharedMetadata) {
+    let expected = match std::fs::read_to_string(input.parent().unwrap().join("metadata.json")) {
+        Ok(expected) => expected,
+        Err(..) => return,
+    };
+    let expected: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&expected).unwrap();
+    let actual = serde_json::to_value(&*metadata.borrow()).unwrap();
+    for (field, value) in expected {
+        assert_eq!(actual[field.as_str()], value, "{}", field);
+    }
+}
+
 #[fixture("tests/fixture/amp/**/input.js")]
 fn amp_attributes_fixture(input: PathBuf) {
     let output = input.parent().unwrap().join("output.js");
@@ -44,11 +66,13 @@ fn next_dynamic_fixture(input: PathBuf) {
                 false,
                 FileName::Real(PathBuf::from("/some-project/src/some-file.js")),
                 Some("/some-project/src".into()),
+                Default::default(),
             )
         },
         &input,
         &output_dev,
     );
+    let metadata = SharedMetadata::default();
     test_fixture(
         syntax(),
         &|_tr| {
@@ -57,11 +81,13 @@ fn next_dynamic_fixture(input: PathBuf) {
                 false,
                 FileName::Real(PathBuf::from("/some-project/src/some-file.js")),
                 Some("/some-project/src".into()),
+                metadata.clone(),
             )
         },
         &input,
         &output_prod,
     );
+    assert_metadata(&input, &metadata);
     test_fixture(
         syntax(),
         &|_tr| {
@@ -70,6 +96,7 @@ fn next_dynamic_fixture(input: PathBuf) {
                 true,
                 FileName::Real(PathBuf::from("/some-project/src/some-file.js")),
                 Some("/some-project/src".into()),
+                Default::default(),
             )
         },
         &input,
@@ -101,7 +128,7 @@ fn next_ssg_fixture(input: PathBuf) {
                 },
                 top_level_mark,
             );
-            chain!(next_ssg(), jsx)
+            chain!(next_ssg(false, Default::default()), jsx)
         },
         &input,
         &output,
@@ -111,6 +138,7 @@ fn next_ssg_fixture(input: PathBuf) {
 #[fixture("tests/fixture/styled-jsx/**/input.js")]
 fn styled_jsx_fixture(input: PathBuf) {
     let output = input.parent().unwrap().join("output.js");
+    let metadata = SharedMetadata::default();
     test_fixture(
         syntax(),
         &|t| {
@@ -118,13 +146,15 @@ fn styled_jsx_fixture(input: PathBuf) {
                 resolver(),
                 styled_jsx(
                     t.cm.clone(),
-                    FileName::Real(PathBuf::from("/some-project/src/some-file.js"))
+                    FileName::Real(PathBuf::from("/some-project/src/some-file.js")),
+                    metadata.clone()
                 )
             )
         },
         &input,
         &output,
     );
+    assert_metadata(&input, &metadata);
 
     test_fixture(
         syntax(),
@@ -142,7 +172,8 @@ fn styled_jsx_fixture(input: PathBuf) {
                 resolver(),
                 styled_jsx(
                     t.cm.clone(),
-                    FileName::Real(PathBuf::from("/some-project/src/some-file.js"))
+                    FileName::Real(PathBuf::from("/some-project/src/some-file.js")),
+                    Default::default()
                 )
             )
         },
@@ -158,12 +189,49 @@ impl swc_ecmascript::visit::VisitMut for DropSpan {
     }
 }
 
+#[fixture("tests/fixture/ssg-report/**/input.js")]
+fn next_ssg_report_fixture(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    let metadata = SharedMetadata::default();
+    test_fixture(
+        syntax(),
+        &|_tr| next_ssg(false, metadata.clone()),
+        &input,
+        &output,
+    );
+    assert_metadata(&input, &metadata);
+}
+
 #[fixture("tests/fixture/page-config/**/input.js")]
 fn page_config_fixture(input: PathBuf) {
     let output = input.parent().unwrap().join("output.js");
     test_fixture(syntax(), &|_tr| page_config_test(), &input, &output);
 }
 
+#[fixture("tests/fixture/page-config/**/input.ts")]
+fn page_config_typescript_fixture(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    test_fixture(
+        Syntax::Typescript(Default::default()),
+        &|_tr| page_config_test(),
+        &input,
+        &output,
+    );
+}
+
+#[fixture("tests/fixture/page-config/full-config/input.js")]
+fn page_config_metadata_fixture(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    let metadata = SharedMetadata::default();
+    test_fixture(
+        syntax(),
+        &|_tr| page_config(false, true, "", None, metadata.clone()),
+        &input,
+        &output,
+    );
+    assert_metadata(&input, &metadata);
+}
+
 #[fixture("tests/fixture/relay/**/input.ts*")]
 fn relay_no_artifact_dir_fixture(input: PathBuf) {
     let output = input.parent().unwrap().join("output.js");
@@ -172,6 +240,7 @@ fn relay_no_artifact_dir_fixture(input: PathBuf) {
         artifact_directory: Some(PathBuf::from("__generated__")),
         ..Default::default()
     };
+    let metadata = SharedMetadata::default();
     test_fixture(
         syntax(),
         &|_tr| {
@@ -179,11 +248,13 @@ fn relay_no_artifact_dir_fixture(input: PathBuf) {
                 &config,
                 FileName::Real(PathBuf::from("input.tsx")),
                 Some(PathBuf::from("src/pages")),
+                metadata.clone(),
             )
         },
         &input,
         &output,
     );
+    assert_metadata(&input, &metadata);
 }
 
 #[fixture("tests/fixture/remove-console/**/input.js")]
@@ -260,3 +331,152 @@ fn shake_exports_fixture_default(input: PathBuf) {
         &output,
     );
 }
+
+#[fixture("tests/fixture/define/**/input.js")]
+fn define_fixture(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    let config = serde_json::from_value(serde_json::json!({
+        "process.env.NODE_ENV": "production",
+        "process.env.NEXT_PUBLIC_API_URL": "https://example.com",
+        "process.env.NEXT_PUBLIC_CACHE": false,
+        "process.env.NEXT_PUBLIC_*": { "FLAG": true, "NAME": "app" },
+        "process.env.OFFSET": -1,
+        "__DEV__": false,
+        "__BUILD__": { "id": "abc", "pages": ["/", "/about"] },
+        "__FEATURES__.enabled": true,
+    }))
+    .unwrap();
+    test_fixture(
+        syntax(),
+        &|_tr| chain!(resolver(), define(&config)),
+        &input,
+        &output,
+    );
+}
+
+#[fixture("tests/fixture/typeof-window/**/input.js")]
+fn typeof_window_fixture(input: PathBuf) {
+    let output_server = input.parent().unwrap().join("output-server.js");
+    let output_client = input.parent().unwrap().join("output-client.js");
+    let config = TypeofWindowConfig { document: true };
+    test_fixture(
+        syntax(),
+        &|_tr| chain!(resolver(), typeof_window(config.clone(), true)),
+        &input,
+        &output_server,
+    );
+    test_fixture(
+        syntax(),
+        &|_tr| chain!(resolver(), typeof_window(config.clone(), false)),
+        &input,
+        &output_client,
+    );
+}
+
+#[fixture("tests/fixture/modularize-imports/builtin/input.js")]
+fn modularize_imports_builtin_fixture(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    let config = ModularizeImportsConfig::default();
+    test_fixture(
+        syntax(),
+        &|_tr| modularize_imports(&config),
+        &input,
+        &output,
+    );
+}
+
+#[fixture("tests/fixture/modularize-imports/custom/input.js")]
+fn modularize_imports_custom_fixture(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    let config = modularize_imports_config();
+    test_fixture(
+        syntax(),
+        &|_tr| modularize_imports(&config),
+        &input,
+        &output,
+    );
+}
+
+fn modularize_imports_config() -> ModularizeImportsConfig {
+    serde_json::from_value(serde_json::json!({
+        "my-icons": {
+            "transform": "my-icons/{{ kebabCase member }}",
+            "preventFullImport": true
+        },
+        "my-components": {
+            "transform": "my-components/esm/{{lowerCase member}}",
+            "skipDefaultConversion": true
+        },
+        "lodash": {
+            "transform": "lodash-es",
+            "skipDefaultConversion": true
+        },
+        "date-fns": false
+    }))
+    .unwrap()
+}
+
+#[fixture("tests/fixture/optimize-barrels/**/input.js")]
+fn optimize_barrels_fixture(input: PathBuf) {
+    let input = input.canonicalize().unwrap();
+    let output = input.parent().unwrap().join("output.js");
+    let config = OptimizeBarrelsConfig {
+        packages: vec!["../ui".into(), "../ui-side-effects".into()],
+    };
+    let metadata = SharedMetadata::default();
+    test_fixture(
+        syntax(),
+        &|_tr| {
+            optimize_barrels(
+                &config,
+                FileName::Real(input.clone()),
+                false,
+                metadata.clone(),
+            )
+        },
+        &input,
+        &output,
+    );
+    assert_metadata(&input, &metadata);
+}
+
+#[fixture("tests/fixture/server-components/**/input.js")]
+fn server_components_fixture(input: PathBuf) {
+    let output_server = input.parent().unwrap().join("output-server.js");
+    let output_client = input.parent().unwrap().join("output-client.js");
+
+    for (is_server, output) in [(true, output_server), (false, output_client)] {
+        let metadata = SharedMetadata::default();
+        test_fixture(
+            syntax(),
+            &|_tr| {
+                server_components(
+                    FileName::Real(PathBuf::from("/some-project/src/some-file.js")),
+                    is_server,
+                    metadata.clone(),
+                )
+            },
+            &input,
+            &output,
+        );
+        assert_metadata(&input, &metadata);
+    }
+}
+
+#[cfg(feature = "plugin")]
+#[fixture("tests/fixture/plugin/**/input.js")]
+fn plugin_fixture(input: PathBuf) {
+    use next_swc::plugin::{plugins, PluginConfig};
+
+    let output = input.parent().unwrap().join("output.js");
+    let config = vec![PluginConfig {
+        path: PathBuf::from("tests/plugins/identity.wat"),
+        config: Default::default(),
+    }];
+    test_fixture(
+        syntax(),
+        &|_tr| plugins(&config, FileName::Anon, false, false),
+        &input,
+        &output,
+    );
+}
@@ -0,0 +1,18 @@
+import { dev } from './dev'
+
+export default function Page() {
+  if (process.env.NODE_ENV !== 'production') {
+    dev()
+  }
+
+  return process.env.NEXT_PUBLIC_API_URL + '/pages'
+}
+
+export const mode = process.env['NODE_ENV'] === 'production' ? 'prod' : 'dev'
+export const cache = process.env.NEXT_PUBLIC_CACHE && createCache()
+export const unknown = process.env.UNKNOWN
+export const flag = process.env.NEXT_PUBLIC_FLAG
+export const unset = process.env.NEXT_PUBLIC_UNSET
+export const offset = 1 - process.env.OFFSET
+export const initial = process.env.NEXT_PUBLIC_NAME.charAt(0)
+export const length = process.env.NEXT_PUBLIC_NAME.length
@@ -0,0 +1,14 @@
+import { dev } from './dev'
+
+export default function Page() {
+  return 'https://example.com' + '/pages'
+}
+
+export const mode = 'prod'
+export const cache = false
+export const unknown = process.env.UNKNOWN
+export const flag = true
+export const unset = void 0
+export const offset = 1 - (-1)
+export const initial = 'app'.charAt(0)
+export const length = 'app'.length
@@ -0,0 +1,13 @@
+if (typeof __DEV__ === 'undefined') {
+  console.log('undefined')
+} else if (__DEV__) {
+  console.log('dev')
+} else {
+  console.log('prod')
+}
+
+const config = { __BUILD__, features: __FEATURES__.enabled }
+
+function shadowed(__DEV__) {
+  return __DEV__
+}
@@ -0,0 +1,13 @@
+console.log('prod')
+
+const config = {
+  __BUILD__: ({
+    'id': 'abc',
+    'pages': ['/', '/about'],
+  }),
+  features: true,
+}
+
+function shadowed(__DEV__) {
+  return __DEV__
+}
@@ -0,0 +1,15 @@
+if (process.env.NODE_ENV !== 'production') {
+  var warned = false
+  var { level } = config
+  function check() {
+    var inner = 1
+  }
+} else {
+  console.log('prod')
+}
+
+if (__DEV__) {
+  for (var i = 0; i < 1; i++) {}
+}
+
+export { warned, level, i }
@@ -0,0 +1,10 @@
+{
+  var warned, level
+  {
+    console.log('prod')
+  }
+}
+
+var i
+
+export { warned, level, i }
@@ -0,0 +1,5 @@
+process.env.NODE_ENV = 'test'
+delete process.env.NODE_ENV
+__DEV__++
+const env = process?.env.NODE_ENV
+const dev = process.env.NODE_ENV === 'development' || process.env.DEBUG
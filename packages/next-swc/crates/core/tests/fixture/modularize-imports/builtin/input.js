@@ -0,0 +1,6 @@
+import { debounce, throttle as throttleFn } from 'lodash'
+import _, { chunk } from 'lodash'
+import { default as lodash, map } from 'lodash'
+import { AccessAlarm, ThreeDRotation } from '@mui/icons-material'
+import { format } from 'date-fns'
+import { useState } from 'react'
@@ -0,0 +1,10 @@
+import debounce from 'lodash/debounce'
+import throttleFn from 'lodash/throttle'
+import _ from 'lodash'
+import chunk from 'lodash/chunk'
+import { default as lodash } from 'lodash'
+import map from 'lodash/map'
+import AccessAlarm from '@mui/icons-material/AccessAlarm'
+import ThreeDRotation from '@mui/icons-material/ThreeDRotation'
+import format from 'date-fns/format'
+import { useState } from 'react'
@@ -0,0 +1,4 @@
+import { ArrowLeft, XCircle as Close, HTMLElement } from 'my-icons'
+import { Button } from 'my-components'
+import { debounce } from 'lodash'
+import { format } from 'date-fns'
@@ -0,0 +1,6 @@
+import ArrowLeft from 'my-icons/arrow-left'
+import Close from 'my-icons/x-circle'
+import HTMLElement from 'my-icons/html-element'
+import { Button } from 'my-components/esm/button'
+import { debounce } from 'lodash-es'
+import { format } from 'date-fns'
@@ -0,0 +1,3 @@
+import theme, { Button, Card, Header as CardHeader, Icon as UiIcon, colors, hooks, cx, Other } from '../ui'
+import * as ui from '../ui'
+import { useState } from 'react'
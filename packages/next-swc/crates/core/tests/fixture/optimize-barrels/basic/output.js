@@ -0,0 +1,11 @@
+import { Other } from '../ui'
+import theme from '../ui/theme.js'
+import { Button } from '../ui/button.js'
+import Card from '../ui/card.js'
+import { CardHeader } from '../ui/card.js'
+import { Icon as UiIcon } from '../ui/icon.js'
+import * as colors from '../ui/colors.js'
+import * as hooks from '../ui/hooks.js'
+import { cx } from '../ui/node_modules/ui-utils/index.js'
+import * as ui from '../ui'
+import { useState } from 'react'
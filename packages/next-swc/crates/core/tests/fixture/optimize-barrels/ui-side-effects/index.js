@@ -0,0 +1,3 @@
+import './styles.css'
+
+export { Button } from './button'
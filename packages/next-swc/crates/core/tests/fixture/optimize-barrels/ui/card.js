@@ -0,0 +1,2 @@
+export default function Card() {}
+export function CardHeader() {}
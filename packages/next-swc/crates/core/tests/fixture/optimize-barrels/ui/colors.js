@@ -0,0 +1 @@
+export const red = '#f00'
@@ -0,0 +1,12 @@
+'use strict'
+
+import { Icon } from './icon'
+import * as colors from './colors'
+
+export { Button } from './button'
+export { default as Card, CardHeader as Header } from './card'
+export { default } from './theme'
+export * as hooks from './hooks'
+export * from './other'
+export { cx } from 'ui-utils'
+export { Icon, colors }
@@ -0,0 +1 @@
+export const Other = null
@@ -0,0 +1,5 @@
+export const config = { amp: true } as const
+
+export default function About() {
+  return null
+}
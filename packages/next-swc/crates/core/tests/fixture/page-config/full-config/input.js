@@ -0,0 +1,15 @@
+export const config = {
+  api: {
+    bodyParser: { sizeLimit: '1mb' },
+    responseLimit: 8000000,
+    externalResolver: true,
+  },
+  runtime: 'nodejs',
+  regions: ['iad1', 'sfo1'],
+  'unstable_runtimeJS': false,
+  unstable_JsPreload: false,
+}
+
+export default function handler(req, res) {
+  res.end()
+}
@@ -0,0 +1,9 @@
+const pageConfig = { amp: true }
+
+function About(props) {
+  return <h3>My AMP About Page!</h3>
+}
+
+export { pageConfig as config }
+
+export default About
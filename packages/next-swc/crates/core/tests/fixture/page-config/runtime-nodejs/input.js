@@ -0,0 +1,7 @@
+import fs from 'fs'
+
+export const config = { runtime: 'nodejs' }
+
+export default function handler(req, res) {
+  res.send(fs.readFileSync(process.cwd() + '/data.json'))
+}
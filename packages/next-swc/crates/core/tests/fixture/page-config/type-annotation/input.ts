@@ -0,0 +1,7 @@
+import type { PageConfig } from 'next'
+
+export const config: PageConfig = { amp: true }
+
+export default function About() {
+  return null
+}
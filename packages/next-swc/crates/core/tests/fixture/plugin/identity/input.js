@@ -0,0 +1,4 @@
+import React from 'react'
+export default function Home() {
+  return <div className="home">Hello</div>
+}
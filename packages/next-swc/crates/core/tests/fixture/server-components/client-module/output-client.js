@@ -0,0 +1,16 @@
+'use client'
+
+import { useState } from 'react'
+
+export default function Counter() {
+  const [count, setCount] = useState(0)
+  return <button onClick={() => setCount(count + 1)}>{count}</button>
+}
+
+export const { Provider, Consumer } = createContext()
+export function Label({ children }) {
+  return <span>{children}</span>
+}
+
+const formatDate = (date) => date.toISOString()
+export { formatDate as 'format-date' }
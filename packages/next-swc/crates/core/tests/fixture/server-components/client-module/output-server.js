@@ -0,0 +1,26 @@
+export default {
+  $$typeof: Symbol.for('react.module.reference'),
+  filepath: '/some-project/src/some-file.js',
+  name: 'default',
+}
+export const Provider = {
+  $$typeof: Symbol.for('react.module.reference'),
+  filepath: '/some-project/src/some-file.js',
+  name: 'Provider',
+}
+export const Consumer = {
+  $$typeof: Symbol.for('react.module.reference'),
+  filepath: '/some-project/src/some-file.js',
+  name: 'Consumer',
+}
+export const Label = {
+  $$typeof: Symbol.for('react.module.reference'),
+  filepath: '/some-project/src/some-file.js',
+  name: 'Label',
+}
+const _reference = {
+  $$typeof: Symbol.for('react.module.reference'),
+  filepath: '/some-project/src/some-file.js',
+  name: 'format-date',
+}
+export { _reference as 'format-date' }
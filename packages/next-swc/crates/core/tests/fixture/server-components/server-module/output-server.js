@@ -0,0 +1,6 @@
+'use strict'
+'use server'
+
+export async function save(data) {
+  await db.insert(data)
+}
@@ -0,0 +1,30 @@
+import { db } from '../lib/db'
+import { formatDate } from '../lib/format'
+import { logger } from '../lib/logger'
+
+const pageSize = 10
+
+function loadPosts() {
+  return db.post.findMany({ take: pageSize })
+}
+
+export async function getStaticProps() {
+  logger.info('loading posts')
+  const posts = await loadPosts()
+  return {
+    props: {
+      posts: posts.map((post) => ({ ...post, date: formatDate(post.date) })),
+    },
+  }
+}
+
+export default function Posts({ posts }) {
+  logger.info('rendering posts')
+  return (
+    <ul>
+      {posts.map((post) => (
+        <li key={post.id}>{post.title}</li>
+      ))}
+    </ul>
+  )
+}
@@ -0,0 +1,12 @@
+import { logger } from '../lib/logger'
+export var __N_SSG = true
+export default function Posts({ posts }) {
+  logger.info('rendering posts')
+  return (
+    <ul>
+      {posts.map((post) => (
+        <li key={post.id}>{post.title}</li>
+      ))}
+    </ul>
+  )
+}
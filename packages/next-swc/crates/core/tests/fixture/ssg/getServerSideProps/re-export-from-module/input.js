@@ -0,0 +1,5 @@
+export { getServerSideProps } from '../lib/data'
+
+export default function Page({ user }) {
+  return <div>{user.name}</div>
+}
@@ -0,0 +1,4 @@
+export var __N_SSP = true;
+export default function Page({ user  }) {
+    return __jsx("div", null, user.name);
+}
@@ -0,0 +1,12 @@
+import { db } from '../lib/db'
+
+async function fetchData() {
+  const user = await db.user.findFirst()
+  return { props: { user } }
+}
+
+export { fetchData as getServerSideProps }
+
+export default function Page({ user }) {
+  return <div>{user.name}</div>
+}
@@ -0,0 +1,5 @@
+export { getStaticPaths, getStaticProps } from '../../lib/posts'
+
+export default function Post({ post }) {
+  return <article>{post.title}</article>
+}
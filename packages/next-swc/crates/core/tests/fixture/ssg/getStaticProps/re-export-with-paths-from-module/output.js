@@ -0,0 +1,4 @@
+export var __N_SSG = true;
+export default function Post({ post  }) {
+    return __jsx("article", null, post.title);
+}
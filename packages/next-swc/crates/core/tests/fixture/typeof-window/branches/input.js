@@ -0,0 +1,14 @@
+import { getSecret } from 'server-sdk'
+import { track } from 'analytics'
+
+export default function Page() {
+  let data
+  if (typeof window === 'undefined') {
+    data = getSecret()
+  } else {
+    track('render')
+  }
+
+  const root = typeof document !== 'undefined' ? document.body : null
+  return { data, root }
+}
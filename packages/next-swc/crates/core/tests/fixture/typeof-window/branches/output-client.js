@@ -0,0 +1,11 @@
+import { track } from 'analytics'
+
+export default function Page() {
+  let data
+  {
+    track('render')
+  }
+
+  const root = document.body
+  return { data, root }
+}
@@ -0,0 +1,11 @@
+import { getSecret } from 'server-sdk'
+
+export default function Page() {
+  let data
+  {
+    data = getSecret()
+  }
+
+  const root = null
+  return { data, root }
+}
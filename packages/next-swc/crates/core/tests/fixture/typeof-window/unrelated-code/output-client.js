@@ -0,0 +1,14 @@
+import { unused } from 'unused'
+import { helper } from 'helpers'
+import 'side-effect'
+
+function neverCalled() {
+  return helper()
+}
+
+export default function Page() {
+  {
+    helper()
+  }
+  return null
+}
@@ -0,0 +1,11 @@
+import { unused } from 'unused'
+import { helper } from 'helpers'
+import 'side-effect'
+
+function neverCalled() {
+  return helper()
+}
+
+export default function Page() {
+  return null
+}
@@ -0,0 +1,8 @@
+import { log } from 'logger'
+
+export default function Page() {
+  if ('production' === 'production') {
+    log('page')
+  }
+  return null
+}
@@ -1,10 +1,21 @@
-use next_swc::{custom_before_pass, TransformOptions};
+use next_swc::{
+    custom_before_pass,
+    diagnostics::{try_with_diagnostics, Fix, Position},
+    disallow_re_export_all_in_page::disallow_re_export_all_in_page,
+    pipeline::{PassName, Pipeline},
+    TransformOptions,
+};
 use serde::de::DeserializeOwned;
+use std::fs;
 use std::path::{Path, PathBuf};
-use swc::Compiler;
+use std::sync::Arc;
+use swc::{config::ModuleConfig, Compiler};
+use swc_common::{FileName, FilePathMapping, SourceMap};
 use swc_ecmascript::{
+    ast::EsVersion,
     parser::{Syntax, TsConfig},
-    transforms::pass::noop,
+    transforms::{pass::noop, resolver},
+    visit::FoldWith,
 };
 use testing::{NormalizedOutput, Tester};
 
@@ -18,73 +29,407 @@ fn loader(input: PathBuf) {
     test(&input, false);
 }
 
+/// Runs every pass of the default pipeline on a page twice and checks that
+/// the outputs are byte-identical and match `output.js`.
+#[testing::fixture("tests/deterministic/**/input.js")]
+fn deterministic(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    let mut options = options(&output, false);
+    options.swc.config.jsc.target = Some(EsVersion::Es2020);
+    options.is_page_file = true;
+    options.is_development = false;
+    // The marker of AMP-only pages ends with it.
+    options.source_date_epoch = Some(String::from("1640995200"));
+
+    let first = transform(&input, &options);
+    let second = transform(&input, &options);
+    assert_eq!(first, second);
+
+    let expected = fs::read_to_string(&output).unwrap();
+    assert_eq!(reprint(&first, &options), reprint(&expected, &options));
+}
+
 fn test(input: &Path, minify: bool) {
     let output = input.parent().unwrap().join("output.js");
+    let code = transform(input, &options(&output, minify));
+
+    NormalizedOutput::from(code)
+        .compare_to_file(output)
+        .unwrap();
+}
 
+fn options(output: &Path, minify: bool) -> TransformOptions {
+    TransformOptions {
+        swc: swc::config::Options {
+            swcrc: true,
+            is_module: swc::config::IsModule::Bool(true),
+            output_path: Some(output.to_path_buf()),
+
+            config: swc::config::Config {
+                jsc: swc::config::JscConfig {
+                    minify: if minify {
+                        Some(assert_json("{ \"compress\": true, \"mangle\": true }"))
+                    } else {
+                        None
+                    },
+                    syntax: Some(Syntax::Typescript(TsConfig {
+                        tsx: true,
+                        ..Default::default()
+                    })),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        disable_next_ssg: false,
+        disable_page_config: false,
+        pages_dir: None,
+        is_page_file: false,
+        is_development: true,
+        is_server: false,
+        styled_components: Some(assert_json("{}")),
+        remove_console: None,
+        react_remove_properties: None,
+        relay: None,
+        shake_exports: None,
+        warn_ssg_retained_imports: false,
+        define: Default::default(),
+        modularize_imports: None,
+        server_components: false,
+        environment_imports: Default::default(),
+        optimize_barrels: None,
+        typeof_window: None,
+        plugins: vec![],
+        json_diagnostics: false,
+        source_date_epoch: None,
+        trace: None,
+        pipeline: Default::default(),
+    }
+}
+
+/// Runs [custom_before_pass] and the rest of the swc pipeline on `input`.
+fn transform(input: &Path, options: &TransformOptions) -> String {
     Tester::new()
         .print_errors(|cm, handler| {
             let c = Compiler::new(cm.clone());
 
             let fm = cm.load_file(input).expect("failed to load file");
 
-            let options = TransformOptions {
-                swc: swc::config::Options {
-                    swcrc: true,
-                    is_module: swc::config::IsModule::Bool(true),
-                    output_path: Some(output.to_path_buf()),
-
-                    config: swc::config::Config {
-                        jsc: swc::config::JscConfig {
-                            minify: if minify {
-                                Some(assert_json("{ \"compress\": true, \"mangle\": true }"))
-                            } else {
-                                None
-                            },
-                            syntax: Some(Syntax::Typescript(TsConfig {
-                                tsx: true,
-                                ..Default::default()
-                            })),
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                },
-                disable_next_ssg: false,
-                disable_page_config: false,
-                pages_dir: None,
-                is_page_file: false,
-                is_development: true,
-                is_server: false,
-                styled_components: Some(assert_json("{}")),
-                remove_console: None,
-                react_remove_properties: None,
-                relay: None,
-                shake_exports: None,
-            };
-
-            let options = options.patch(&fm);
+            let program = options
+                .parse(&c, fm.clone(), &handler)
+                .expect("failed to parse file");
+            let options = c.run(|| options.clone().patch(&program, &handler));
 
             match c.process_js_with_custom_pass(
                 fm.clone(),
-                None,
+                Some(program),
                 &handler,
                 &options.swc,
-                |_| custom_before_pass(cm.clone(), fm.clone(), &options),
+                |_| custom_before_pass(cm.clone(), fm.clone(), &options, Default::default()),
                 |_| noop(),
             ) {
-                Ok(v) => {
-                    NormalizedOutput::from(v.code)
-                        .compare_to_file(output)
-                        .unwrap();
-                }
+                Ok(v) => Ok(v.code),
                 Err(err) => panic!("Error: {:?}", err),
-            };
+            }
+        })
+        .expect("failed")
+}
+
+/// Prints `code` again without the Next.js passes, so outputs can be
+/// compared regardless of how they are formatted.
+fn reprint(code: &str, options: &TransformOptions) -> String {
+    Tester::new()
+        .print_errors(|cm, handler| {
+            let c = Compiler::new(cm.clone());
+            let fm = cm.new_source_file(FileName::Anon, code.into());
+
+            let mut options = options.swc.clone();
+            options.swcrc = false;
+            match c.process_js_with_custom_pass(
+                fm,
+                None,
+                &handler,
+                &options,
+                |_| noop(),
+                |_| noop(),
+            ) {
+                Ok(v) => Ok(v.code),
+                Err(err) => panic!("Error: {:?}", err),
+            }
+        })
+        .expect("failed")
+}
+
+#[test]
+fn auto_cjs() {
+    for (src, is_cjs) in [
+        ("module.exports = 1", true),
+        ("module['exports'] = 1", true),
+        ("exports.foo = 1", true),
+        (
+            "Object.defineProperty(exports, '__esModule', { value: true })",
+            true,
+        ),
+        ("const foo = require('foo')", true),
+        ("import foo from 'foo'; const bar = require('bar')", false),
+        ("import foo from 'foo'; module.exports = foo", true),
+        ("export default 1", false),
+        // Variables which shadow the globals.
+        ("const module = {}; module.exports = 1", false),
+        ("function f(exports) { exports.foo = 1 }", false),
+        ("function f(module) { module.exports = 1 }", false),
+        (
+            "let exports = {}; Object.defineProperty(exports, 'a', {})",
+            false,
+        ),
+        (
+            "class Object {}; Object.defineProperty(exports, 'a', {})",
+            false,
+        ),
+        ("function f(module) {} module.exports = f", true),
+        ("function f(require) { require('foo') }", false),
+    ] {
+        Tester::new()
+            .print_errors(|cm, handler| {
+                let c = Compiler::new(cm.clone());
+                let fm = cm.new_source_file(FileName::Anon, src.into());
 
+                let options: TransformOptions = assert_json("{}");
+                let program = options.parse(&c, fm, &handler).unwrap();
+                let options = c.run(|| options.patch(&program, &handler));
+
+                assert_eq!(
+                    matches!(options.swc.config.module, Some(ModuleConfig::CommonJs(..))),
+                    is_cjs,
+                    "{}",
+                    src
+                );
+
+                Ok(())
+            })
+            .unwrap();
+    }
+}
+
+#[test]
+fn pipeline() {
+    let pipeline: Pipeline = assert_json(r#"{ "disable": ["hook_optimizer", "amp_attributes"] }"#);
+    assert!(!pipeline.contains(PassName::HookOptimizer));
+    assert!(!pipeline.contains(PassName::AmpAttributes));
+    assert!(pipeline.contains(PassName::NextSsg));
+
+    let pipeline: Pipeline =
+        assert_json(r#"{ "passes": ["styled_jsx", "page_config", "next_ssg"] }"#);
+    assert_eq!(
+        pipeline.passes().collect::<Vec<_>>(),
+        vec![PassName::StyledJsx, PassName::PageConfig, PassName::NextSsg]
+    );
+
+    assert!(Pipeline::new(PassName::DEFAULT.to_vec()).is_ok());
+
+    let pipeline: Pipeline = assert_json(r#"{ "passes": ["define", "next_ssg", "page_config"] }"#);
+    assert!(pipeline.contains(PassName::NextSsg));
+
+    for invalid in [
+        r#"{ "passes": ["next_ssg", "next_ssg"] }"#,
+        r#"{ "passes": ["next_ssg", "styled_jsx"] }"#,
+        r#"{ "passes": ["unknown"] }"#,
+        r#"{ "passes": ["next_ssg", "define"] }"#,
+        r#"{ "passes": ["shake_exports", "next_ssg"] }"#,
+        r#"{ "passes": ["edge_runtime", "page_config"] }"#,
+        r#"{ "passes": ["environment_imports", "next_ssg"] }"#,
+    ] {
+        assert!(
+            serde_json::from_str::<Pipeline>(invalid).is_err(),
+            "{}",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn legacy_disable_options() {
+    let options: TransformOptions =
+        assert_json(r#"{ "disableNextSsg": true, "disablePageConfig": true }"#);
+    let passes = options.passes().collect::<Vec<_>>();
+    assert!(!passes.contains(&PassName::NextSsg));
+    assert!(!passes.contains(&PassName::PageConfig));
+    assert!(passes.contains(&PassName::StyledJsx));
+}
+
+#[test]
+fn ssg_retained_imports_warning() {
+    const SRC: &str = "import { db } from '../lib/db'
+
+export async function getServerSideProps() {
+  return { props: { user: await db.user.findFirst() } }
+}
+
+export default function Page({ user }) {
+  db.track('view')
+  return <div>{user.name}</div>
+}
+";
+
+    let codes = |options: &str| {
+        let cm = Arc::new(SourceMap::new(FilePathMapping::empty()));
+        let c = Compiler::new(cm.clone());
+        let fm = cm.new_source_file(FileName::Real("pages/index.js".into()), SRC.into());
+        let options: TransformOptions = assert_json(options);
+
+        let (res, diagnostics) = try_with_diagnostics(cm.clone(), true, |handler| {
+            c.run(|| {
+                let program = options.parse(&c, fm.clone(), handler)?;
+                program
+                    .fold_with(&mut resolver())
+                    .fold_with(&mut custom_before_pass(
+                        cm.clone(),
+                        fm,
+                        &options,
+                        Default::default(),
+                    ));
+                Ok(())
+            })
+        });
+        res.unwrap();
+
+        diagnostics
+            .into_iter()
+            .filter_map(|diagnostic| diagnostic.code)
+            .collect::<Vec<_>>()
+    };
+
+    let syntax = r#""jsc": { "parser": { "syntax": "ecmascript", "jsx": true } }"#;
+    assert_eq!(
+        codes(&format!(r#"{{ {}, "isDevelopment": true }}"#, syntax)),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        codes(&format!(
+            r#"{{ {}, "warnSsgRetainedImports": true }}"#,
+            syntax
+        )),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        codes(&format!(
+            r#"{{ {}, "isDevelopment": true, "warnSsgRetainedImports": true }}"#,
+            syntax
+        )),
+        vec!["ssg-import-retained"]
+    );
+}
+
+#[test]
+fn diagnostics_json() {
+    let cm = Arc::new(SourceMap::new(FilePathMapping::empty()));
+    let c = Compiler::new(cm.clone());
+    let fm = cm.new_source_file(
+        FileName::Real("pages/index.js".into()),
+        "module.exports = {}\nexport * from './a'\n".into(),
+    );
+    let options: TransformOptions = assert_json("{}");
+
+    let (res, diagnostics) = try_with_diagnostics(cm.clone(), true, |handler| {
+        c.run(|| {
+            let program = options.parse(&c, fm, handler)?;
+            let _ = options.clone().patch(&program, handler);
+            program.fold_with(&mut disallow_re_export_all_in_page(true));
+            Ok(())
+        })
+    });
+
+    assert!(res.is_err());
+    assert_eq!(
+        serde_json::to_value(&diagnostics).unwrap(),
+        serde_json::json!([
+            {
+                "code": "mixed-module-syntax",
+                "severity": "warning",
+                "file": "pages/index.js",
+                "start": { "line": 1, "column": 0 },
+                "end": { "line": 1, "column": 14 },
+                "message": "CommonJS exports are used together with ES module syntax. The file is \
+                            compiled as CommonJS, so `export` declarations and `module.exports` \
+                            may overwrite each other.",
+                "docsUrl": null,
+                "fixes": [],
+            },
+            {
+                "code": "export-all-in-page",
+                "severity": "error",
+                "file": "pages/index.js",
+                "start": { "line": 2, "column": 0 },
+                "end": { "line": 2, "column": 19 },
+                "message": "Using `export * from '...'` in a page is disallowed. Please use \
+                            `export { default } from '...'` instead.\nRead more: \
+                            https://nextjs.org/docs/messages/export-all-in-page",
+                "docsUrl": "https://nextjs.org/docs/messages/export-all-in-page",
+                "fixes": [
+                    {
+                        "message": "Re-export only the default export",
+                        "edits": [
+                            {
+                                "start": { "line": 2, "column": 0 },
+                                "end": { "line": 2, "column": 19 },
+                                "replacement": "export { default } from './a'",
+                            },
+                        ],
+                    },
+                ],
+            },
+        ])
+    );
+}
+
+#[test]
+fn apply_export_all_fix() {
+    let src = r#"export * from "./it's\\dir""#;
+
+    let cm = Arc::new(SourceMap::new(FilePathMapping::empty()));
+    let c = Compiler::new(cm.clone());
+    let fm = cm.new_source_file(FileName::Real("pages/index.js".into()), src.into());
+    let options: TransformOptions = assert_json("{}");
+
+    let (_, diagnostics) = try_with_diagnostics(cm.clone(), true, |handler| {
+        c.run(|| {
+            let program = options.parse(&c, fm, handler)?;
+            program.fold_with(&mut disallow_re_export_all_in_page(true));
             Ok(())
         })
-        .map(|_| ())
-        .expect("failed");
+    });
+
+    assert_eq!(
+        apply_fix(src, &diagnostics[0].fixes[0]),
+        r#"export { default } from './it\'s\\dir'"#
+    );
+}
+
+/// Applies the edits of `fix` to `src`.
+fn apply_fix(src: &str, fix: &Fix) -> String {
+    let offset = |pos: &Position| {
+        let line_start: usize = src
+            .split_inclusive('\n')
+            .take(pos.line - 1)
+            .map(str::len)
+            .sum();
+        line_start
+            + src[line_start..]
+                .chars()
+                .take(pos.column)
+                .map(char::len_utf8)
+                .sum::<usize>()
+    };
+
+    let mut edits = fix.edits.iter().collect::<Vec<_>>();
+    edits.sort_by_key(|edit| std::cmp::Reverse(offset(&edit.start)));
+
+    let mut fixed = src.to_string();
+    for edit in edits {
+        fixed.replace_range(offset(&edit.start)..offset(&edit.end), &edit.replacement);
+    }
+    fixed
 }
 
 /// Using this, we don't have to break code by adding field.s
@@ -0,0 +1,8 @@
+;; Always returns `{ "error": "nope" }`.
+(module
+  (memory (export "memory") 16)
+  (data (i32.const 0) "{\"error\":\"nope\"}")
+  (func (export "alloc") (param $len i32) (result i32)
+    (i32.const 1024))
+  (func (export "transform") (param $ptr i32) (param $len i32) (result i64)
+    (i64.const 16)))
@@ -0,0 +1,9 @@
+;; Returns its input, which deserializes to `{ program }`.
+(module
+  (memory (export "memory") 16)
+  (func (export "alloc") (param $len i32) (result i32)
+    (i32.const 0))
+  (func (export "transform") (param $ptr i32) (param $len i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+      (i64.extend_i32_u (local.get $len)))))
@@ -1,5 +1,18 @@
 extern crate napi_build;
 
+use std::{env, fs};
+
 fn main() {
     napi_build::setup();
+
+    // The version of the npm package, or of this crate when it's built outside
+    // of the package.
+    let package_json = "../../package.json";
+    println!("cargo:rerun-if-changed={}", package_json);
+    let version = fs::read_to_string(package_json)
+        .ok()
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+        .and_then(|package| package["version"].as_str().map(String::from))
+        .unwrap_or_else(|| env::var("CARGO_PKG_VERSION").unwrap());
+    println!("cargo:rustc-env=NEXT_SWC_VERSION={}", version);
 }
@@ -0,0 +1,323 @@
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::SystemTime,
+};
+use swc_common::sync::Lazy;
+
+/// Version of `@next/swc`, used to invalidate entries written by other builds.
+const VERSION: &str = env!("NEXT_SWC_VERSION");
+
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Indexes of the cache directories used by this process.
+static INDEXES: Lazy<Mutex<HashMap<PathBuf, Index>>> = Lazy::new(Default::default);
+
+fn default_max_size() -> u64 {
+    512 * 1024 * 1024
+}
+
+/// `cache` in the options passed to `transform`. Transforms with
+/// `optimizeBarrels` are not cached, as their output depends on other files.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheOptions {
+    pub dir: PathBuf,
+
+    /// Total size of the entries in bytes. The least recently used entries are
+    /// removed once a write exceeds it.
+    #[serde(default = "default_max_size")]
+    pub max_size: u64,
+}
+
+/// Directory of transform results, one file per entry.
+///
+/// Entries are written to a temporary file and renamed into place, so
+/// concurrent readers never see a partial entry and concurrent writers of the
+/// same key leave one complete copy.
+pub struct TransformCache {
+    dir: PathBuf,
+    max_size: u64,
+}
+
+/// Sizes and use order of the entries of a cache directory, so writes don't
+/// need to list the directory.
+///
+/// The index is loaded from the directory the first time it's used, ordered by
+/// modification time. After that it only sees the entries used by this
+/// process.
+#[derive(Default)]
+struct Index {
+    /// Size and last use of each entry.
+    entries: HashMap<String, (u64, u64)>,
+    /// Keys by last use, least recent first.
+    order: BTreeMap<u64, String>,
+    total: u64,
+    clock: u64,
+}
+
+impl Index {
+    fn load(dir: &Path) -> Self {
+        let mut files = vec![];
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let key = match path.file_name().and_then(|name| name.to_str()) {
+                    Some(key) if !is_tmp(&path) => key.to_string(),
+                    _ => continue,
+                };
+                if let Ok(meta) = entry.metadata() {
+                    if meta.is_file() {
+                        let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                        files.push((modified, key, meta.len()));
+                    }
+                }
+            }
+        }
+        files.sort();
+
+        let mut index = Index::default();
+        for (_, key, len) in files {
+            index.insert(key, len);
+        }
+        index
+    }
+
+    /// Marks `key` as the most recently used entry.
+    fn touch(&mut self, key: &str) {
+        if let Some((len, _)) = self.remove(key) {
+            self.insert(key.to_string(), len);
+        }
+    }
+
+    fn insert(&mut self, key: String, len: u64) {
+        self.remove(&key);
+
+        self.clock += 1;
+        self.total += len;
+        self.order.insert(self.clock, key.clone());
+        self.entries.insert(key, (len, self.clock));
+    }
+
+    /// Returns the size and last use of the removed entry.
+    fn remove(&mut self, key: &str) -> Option<(u64, u64)> {
+        let (len, used) = self.entries.remove(key)?;
+        self.order.remove(&used);
+        self.total -= len;
+        Some((len, used))
+    }
+
+    /// Removes and returns the least recently used entry.
+    fn pop_oldest(&mut self) -> Option<String> {
+        let key = self.order.values().next()?.clone();
+        self.remove(&key);
+        Some(key)
+    }
+}
+
+impl TransformCache {
+    pub fn new(options: CacheOptions) -> Self {
+        TransformCache {
+            dir: options.dir,
+            max_size: options.max_size,
+        }
+    }
+
+    /// Hashes everything which affects the output of a transform: the source,
+    /// the options, the plugins and the environment read by the passes.
+    ///
+    /// Fails if the options are not JSON or a plugin cannot be read.
+    pub fn key(src: &str, options: &str, plugins: &[&Path]) -> io::Result<String> {
+        let options = output_options(options)?;
+        let source_date_epoch = env::var("SOURCE_DATE_EPOCH").unwrap_or_default();
+
+        let mut hasher = Sha1::new();
+        let mut update = |part: &[u8]| {
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        };
+        for part in [VERSION, &*source_date_epoch, &*options, src] {
+            update(part.as_bytes());
+        }
+        for plugin in plugins {
+            update(&fs::read(plugin)?);
+        }
+
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let data = fs::read(self.dir.join(key));
+
+        self.with_index(|index| match &data {
+            Ok(..) => index.touch(key),
+            // Removed by another process.
+            Err(..) => {
+                index.remove(key);
+            }
+        });
+
+        data.ok()
+    }
+
+    pub fn put(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let tmp = self.dir.join(format!(
+            "{}.{}.{}.tmp",
+            key,
+            process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&tmp, data)?;
+        if let Err(err) = fs::rename(&tmp, self.dir.join(key)) {
+            let _ = fs::remove_file(&tmp);
+            return Err(err);
+        }
+
+        self.with_index(|index| {
+            index.insert(key.to_string(), data.len() as u64);
+            self.evict(index)
+        })
+    }
+
+    fn with_index<T>(&self, op: impl FnOnce(&mut Index) -> T) -> T {
+        let mut indexes = INDEXES.lock().unwrap();
+        let index = indexes
+            .entry(self.dir.clone())
+            .or_insert_with(|| Index::load(&self.dir));
+
+        op(index)
+    }
+
+    /// Removes the least recently used entries until the cache fits in
+    /// `max_size`.
+    fn evict(&self, index: &mut Index) -> io::Result<()> {
+        while index.total > self.max_size {
+            let key = match index.pop_oldest() {
+                Some(key) => key,
+                None => break,
+            };
+            match fs::remove_file(self.dir.join(key)) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// `options` without `cache` and `trace`, which don't change the output. The
+/// start time of a trace differs for every call.
+fn output_options(options: &str) -> io::Result<String> {
+    let mut options: serde_json::Value = serde_json::from_str(options)?;
+    if let Some(server) = options.get_mut("server").and_then(|s| s.as_object_mut()) {
+        server.remove("trace");
+    }
+    if let Some(options) = options.as_object_mut() {
+        options.remove("cache");
+        options.remove("trace");
+    }
+
+    Ok(options.to_string())
+}
+
+fn is_tmp(path: &Path) -> bool {
+    path.extension().map_or(false, |ext| ext == "tmp")
+}
+
+#[cfg(test)]
+fn test_cache(name: &str, max_size: u64) -> TransformCache {
+    let dir = env::temp_dir().join(format!("next-swc-cache-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    TransformCache::new(CacheOptions { dir, max_size })
+}
+
+#[test]
+fn test_put_get_evict() {
+    let cache = test_cache("evict", 10);
+
+    let a = TransformCache::key("a", "{}", &[]).unwrap();
+    let b = TransformCache::key("b", "{}", &[]).unwrap();
+    assert_ne!(a, b);
+    assert_ne!(
+        a,
+        TransformCache::key("a", "{\"minify\":true}", &[]).unwrap()
+    );
+
+    cache.put(&a, b"123456").unwrap();
+    assert_eq!(cache.get(&a).as_deref(), Some(&b"123456"[..]));
+    assert_eq!(cache.get(&b), None);
+
+    cache.put(&b, b"123456").unwrap();
+    assert_eq!(cache.get(&a), None);
+    assert_eq!(cache.get(&b).as_deref(), Some(&b"123456"[..]));
+
+    fs::remove_dir_all(&cache.dir).unwrap();
+}
+
+#[test]
+fn test_evict_least_recently_read() {
+    let cache = test_cache("recency", 12);
+
+    cache.put("a", b"1234").unwrap();
+    cache.put("b", b"1234").unwrap();
+    cache.put("c", b"1234").unwrap();
+    // `a` was written first, but read last.
+    assert!(cache.get("a").is_some());
+
+    cache.put("d", b"1234").unwrap();
+    assert!(cache.get("a").is_some());
+    assert_eq!(cache.get("b"), None);
+    assert!(cache.get("c").is_some());
+    assert!(cache.get("d").is_some());
+
+    fs::remove_dir_all(&cache.dir).unwrap();
+}
+
+#[test]
+fn test_key_of_plugins() {
+    let cache = test_cache("plugins", 10);
+    fs::create_dir_all(&cache.dir).unwrap();
+    let plugin = cache.dir.join("plugin.wasm");
+
+    fs::write(&plugin, b"1").unwrap();
+    let before = TransformCache::key("a", "{}", &[plugin.as_path()]).unwrap();
+    fs::write(&plugin, b"2").unwrap();
+    let after = TransformCache::key("a", "{}", &[plugin.as_path()]).unwrap();
+    assert_ne!(before, after);
+    assert_ne!(before, TransformCache::key("a", "{}", &[]).unwrap());
+
+    fs::remove_file(&plugin).unwrap();
+    assert!(TransformCache::key("a", "{}", &[plugin.as_path()]).is_err());
+
+    fs::remove_dir_all(&cache.dir).unwrap();
+}
+
+#[test]
+fn test_key_of_options() {
+    let key = |options: &str| TransformCache::key("a", options, &[]).unwrap();
+
+    let base = key(r#"{"minify":true}"#);
+    assert_eq!(
+        base,
+        key(r#"{"minify":true,"cache":{"dir":"/tmp/other"},"trace":{"startTime":1}}"#)
+    );
+    assert_eq!(
+        key(r#"{"server":{"isServer":true,"trace":{"startTime":1}}}"#),
+        key(r#"{"server":{"isServer":true,"trace":{"startTime":2}}}"#)
+    );
+    assert_ne!(base, key(r#"{"minify":false}"#));
+    assert!(TransformCache::key("a", "{", &[]).is_err());
+}
@@ -35,25 +35,28 @@ extern crate napi_derive;
 extern crate swc_node_base;
 
 use backtrace::Backtrace;
-use napi::{CallContext, Env, JsObject, JsUndefined};
-use std::{env, panic::set_hook, sync::Arc};
+use napi::{CallContext, Env, JsObject, JsUndefined, JsUnknown, Property, Status, ValueType};
+use std::{convert::TryFrom, env, panic::set_hook, sync::Arc};
 use swc::{Compiler, TransformOutput};
 use swc_common::{self, sync::Lazy, FilePathMapping, SourceMap};
 
 mod bundle;
+mod cache;
 mod minify;
 mod parse;
 mod transform;
 mod util;
 
-static COMPILER: Lazy<Arc<Compiler>> = Lazy::new(|| {
+static COMPILER: Lazy<Arc<Compiler>> = Lazy::new(new_compiler);
+
+fn new_compiler() -> Arc<Compiler> {
     let cm = Arc::new(SourceMap::new(FilePathMapping::empty()));
 
     Arc::new(Compiler::new(cm))
-});
+}
 
 #[module_exports]
-fn init(mut exports: JsObject) -> napi::Result<()> {
+fn init(mut exports: JsObject, env: Env) -> napi::Result<()> {
     if cfg!(debug_assertions) || env::var("SWC_DEBUG").unwrap_or_default() == "1" {
         set_hook(Box::new(|panic_info| {
             let backtrace = Backtrace::new();
@@ -65,12 +68,20 @@ fn init(mut exports: JsObject) -> napi::Result<()> {
 
     exports.create_named_method("transform", transform::transform)?;
     exports.create_named_method("transformSync", transform::transform_sync)?;
+    exports.create_named_method("transformMany", transform::transform_many)?;
 
     exports.create_named_method("minify", minify::minify)?;
     exports.create_named_method("minifySync", minify::minify_sync)?;
 
     exports.create_named_method("parse", parse::parse)?;
 
+    let compiler_class = env.define_class(
+        "Compiler",
+        construct_compiler,
+        &[Property::new(&env, "reset")?.with_method(reset_compiler)],
+    )?;
+    exports.set_named_property("Compiler", compiler_class)?;
+
     Ok(())
 }
 
@@ -78,14 +89,100 @@ fn get_compiler(_ctx: &CallContext) -> Arc<Compiler> {
     COMPILER.clone()
 }
 
+/// Returns the compiler of the `Compiler` instance passed at `index`, or the
+/// global one if the argument is omitted, `undefined` or `null`.
+fn get_compiler_at(ctx: &CallContext, index: usize) -> napi::Result<Arc<Compiler>> {
+    if index >= ctx.length {
+        return Ok(get_compiler(ctx));
+    }
+
+    let arg = ctx.get::<JsUnknown>(index)?;
+    if !is_compiler_arg(index, arg.get_type()?)? {
+        return Ok(get_compiler(ctx));
+    }
+    let instance = JsObject::try_from(arg)?;
+    let c: &mut Arc<Compiler> = ctx
+        .env
+        .unwrap(&instance)
+        .map_err(|_| invalid_compiler_arg(index))?;
+
+    Ok(c.clone())
+}
+
+/// Returns whether an optional `Compiler` argument of type `value_type` was
+/// passed, and an error if the argument cannot be a `Compiler`.
+fn is_compiler_arg(index: usize, value_type: ValueType) -> napi::Result<bool> {
+    match value_type {
+        ValueType::Undefined | ValueType::Null => Ok(false),
+        ValueType::Object => Ok(true),
+        _ => Err(invalid_compiler_arg(index)),
+    }
+}
+
+fn invalid_compiler_arg(index: usize) -> napi::Error {
+    napi::Error::new(
+        Status::InvalidArg,
+        format!("Argument {} should be a Compiler", index),
+    )
+}
+
+/// `new Compiler()` creates a compiler with its own `SourceMap`.
 #[js_function]
 fn construct_compiler(ctx: CallContext) -> napi::Result<JsUndefined> {
-    // TODO: Assign swc::Compiler
+    let mut this: JsObject = ctx.this_unchecked();
+    ctx.env.wrap(&mut this, new_compiler())?;
+
     ctx.env.get_undefined()
 }
 
+/// Drops every file known to the compiler. Transforms which are already running
+/// keep using the old `SourceMap`.
+#[js_function]
+fn reset_compiler(ctx: CallContext) -> napi::Result<JsUndefined> {
+    let this: JsObject = ctx.this_unchecked();
+    let c: &mut Arc<Compiler> = ctx.env.unwrap(&this)?;
+    reset(c);
+
+    ctx.env.get_undefined()
+}
+
+fn reset(c: &mut Arc<Compiler>) {
+    *c = new_compiler();
+}
+
 pub fn complete_output(env: &Env, output: TransformOutput) -> napi::Result<JsObject> {
     env.to_js_value(&output)?.coerce_to_object()
 }
 
 pub type ArcCompiler = Arc<Compiler>;
+
+#[test]
+fn test_compiler_arg() {
+    assert!(!is_compiler_arg(3, ValueType::Undefined).unwrap());
+    assert!(!is_compiler_arg(3, ValueType::Null).unwrap());
+    assert!(is_compiler_arg(3, ValueType::Object).unwrap());
+
+    for value_type in [ValueType::Number, ValueType::String, ValueType::Function] {
+        let err = is_compiler_arg(3, value_type).unwrap_err();
+        assert_eq!(err.status, Status::InvalidArg);
+        assert_eq!(err.reason, "Argument 3 should be a Compiler");
+    }
+}
+
+#[test]
+fn test_reset_compiler() {
+    use swc_common::FileName;
+
+    let mut c = new_compiler();
+    assert!(!Arc::ptr_eq(&c.cm, &new_compiler().cm));
+    assert!(!Arc::ptr_eq(&c.cm, &COMPILER.cm));
+
+    c.cm.new_source_file(FileName::Anon, "a".into());
+    let running = c.clone();
+    reset(&mut c);
+
+    assert!(!Arc::ptr_eq(&c, &running));
+    assert_eq!(c.cm.files().len(), 0);
+    // Transforms which are already running keep their files.
+    assert_eq!(running.cm.files().len(), 1);
+}
@@ -27,22 +27,39 @@ DEALINGS IN THE SOFTWARE.
 */
 
 use crate::{
-    complete_output, get_compiler,
+    cache::{CacheOptions, TransformCache},
+    complete_output, get_compiler_at,
     util::{deserialize_json, CtxtExt, MapErr},
 };
 use anyhow::{anyhow, bail, Context as _, Error};
 use napi::{CallContext, Env, JsBoolean, JsBuffer, JsObject, JsString, JsUnknown, Status, Task};
-use next_swc::{custom_before_pass, TransformOptions};
+use next_swc::{
+    diagnostics::{try_with_diagnostics, Diagnostic},
+    metadata::{SharedMetadata, TransformMetadata},
+    shared_before_pass, target_before_pass,
+    trace::{self, with_tracer, Tracer},
+    TransformOptions,
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::fs::read_to_string;
 use std::{
+    cell::RefCell,
+    collections::HashMap,
     convert::TryFrom,
     panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
     sync::Arc,
+    time::Instant,
 };
 use swc::{try_with_handler, Compiler, TransformOutput};
-use swc_common::{FileName, SourceFile};
-use swc_ecmascript::ast::Program;
-use swc_ecmascript::transforms::pass::noop;
+use swc_common::{
+    chain,
+    errors::{DiagnosticBuilder, Emitter, Handler, HANDLER},
+    FileName, SourceFile,
+};
+use swc_ecmascript::ast::{Module, Program, Script};
+use swc_ecmascript::visit::Fold;
 
 /// Input to transform
 #[derive(Debug)]
@@ -57,74 +74,400 @@ pub struct TransformTask {
     pub c: Arc<Compiler>,
     pub input: Input,
     pub options: String,
+    /// When `transform` was called, to line up trace spans with the JS trace.
+    pub started: Instant,
+}
+
+/// [TransformOutput] with the information collected by the Next.js passes.
+#[derive(Serialize)]
+pub struct TransformResult {
+    /// `None` if the transform failed and the caller asked for
+    /// `jsonDiagnostics`.
+    #[serde(flatten)]
+    pub output: Option<TransformOutput>,
+    pub metadata: TransformMetadata,
+    pub diagnostics: Vec<Diagnostic>,
+
+    /// The server build, if the options of both builds were passed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<Box<TransformResult>>,
+}
+
+/// Options of `transform`, which may also enable the on-disk cache and the
+/// server build.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransformTaskOptions {
+    #[serde(default)]
+    cache: Option<CacheOptions>,
+
+    /// Options of the server build, to transform the file for both targets
+    /// with a single parse.
+    #[serde(default)]
+    server: Option<TransformOptions>,
+
+    #[serde(flatten)]
+    options: TransformOptions,
 }
 
 impl Task for TransformTask {
-    type Output = TransformOutput;
+    /// A serialized [TransformResult], which may come from the cache.
+    type Output = serde_json::Value;
     type JsValue = JsObject;
 
     fn compute(&mut self) -> napi::Result<Self::Output> {
-        let res = catch_unwind(AssertUnwindSafe(|| {
-            try_with_handler(self.c.cm.clone(), true, |handler| {
-                self.c.run(|| {
-                    let options: TransformOptions = deserialize_json(&self.options)?;
-                    let fm = match &self.input {
-                        Input::Source { src } => {
-                            let filename = if options.swc.filename.is_empty() {
-                                FileName::Anon
-                            } else {
-                                FileName::Real(options.swc.filename.clone().into())
-                            };
-
-                            self.c.cm.new_source_file(filename, src.to_string())
-                        }
-                        Input::FromFilename => {
-                            let filename = &options.swc.filename;
-                            if filename.is_empty() {
-                                bail!("no filename is provided via options");
-                            }
-
-                            self.c.cm.new_source_file(
-                                FileName::Real(filename.into()),
-                                read_to_string(filename).with_context(|| {
-                                    format!("Failed to read source code from {}", filename)
-                                })?,
-                            )
+        let TransformTaskOptions {
+            cache,
+            server,
+            options,
+        } = deserialize_json(&self.options).convert_err()?;
+
+        // Only source code is cached, as reading the file would cost as much as
+        // hashing it. A plugin which cannot be read fails the transform, so the
+        // result is not cached either. Neither is the output of
+        // `optimize_barrels`, which depends on the barrel files.
+        let plugins: Vec<&Path> = options
+            .plugins
+            .iter()
+            .chain(server.iter().flat_map(|server| &server.plugins))
+            .map(|plugin| &*plugin.path)
+            .collect();
+        let reads_barrels = options.optimize_barrels.is_some()
+            || server
+                .as_ref()
+                .map_or(false, |server| server.optimize_barrels.is_some());
+        let cache = match (cache, &self.input) {
+            (Some(cache), Input::Source { src }) if !reads_barrels => {
+                TransformCache::key(src, &self.options, &plugins)
+                    .ok()
+                    .map(|key| (TransformCache::new(cache), key))
+            }
+            _ => None,
+        };
+        if let Some((cache, key)) = &cache {
+            if let Some(hit) = cache
+                .get(key)
+                .and_then(|data| serde_json::from_slice(&data).ok())
+            {
+                return Ok(hit);
+            }
+        }
+
+        let result = transform_file_traced(&self.c, &self.input, options, server, self.started)
+            .convert_err()?;
+        let value = serde_json::to_value(&result)
+            .context("failed to serialize TransformResult")
+            .convert_err()?;
+
+        if let Some((cache, key)) = &cache {
+            // Failing to write the cache should not fail the build.
+            if result.output.is_some() {
+                let _ = cache.put(key, value.to_string().as_bytes());
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn resolve(self, env: Env, result: Self::Output) -> napi::Result<Self::JsValue> {
+        env.to_js_value(&result)?.coerce_to_object()
+    }
+}
+
+/// Runs the Next.js passes and swc on a single file.
+///
+/// If `server` is set, the file is parsed once and the output of the server
+/// build is returned in [TransformResult::server], with the diagnostics of the
+/// server build. Diagnostics which do not depend on the target, like those of
+/// parsing and of [shared_before_pass], are only reported for the client.
+fn transform_file(
+    c: &Compiler,
+    input: &Input,
+    options: TransformOptions,
+    server: Option<TransformOptions>,
+) -> Result<TransformResult, Error> {
+    let json_diagnostics = options.json_diagnostics;
+
+    let (res, diagnostics) = catch_unwind(AssertUnwindSafe(|| {
+        try_with_diagnostics(c.cm.clone(), true, |handler| {
+            c.run(|| {
+                let fm = match input {
+                    Input::Source { src } => {
+                        let filename = if options.swc.filename.is_empty() {
+                            FileName::Anon
+                        } else {
+                            FileName::Real(options.swc.filename.clone().into())
+                        };
+
+                        c.cm.new_source_file(filename, src.to_string())
+                    }
+                    Input::FromFilename => {
+                        let filename = &options.swc.filename;
+                        if filename.is_empty() {
+                            bail!("no filename is provided via options");
                         }
-                    };
-                    let options = options.patch(&fm);
-
-                    let before_pass = custom_before_pass(self.c.cm.clone(), fm.clone(), &options);
-                    self.c.process_js_with_custom_pass(
-                        fm,
-                        None,
-                        handler,
-                        &options.swc,
-                        |_| before_pass,
-                        |_| noop(),
-                    )
-                })
+
+                        c.cm.new_source_file(
+                            FileName::Real(filename.into()),
+                            read_to_string(filename).with_context(|| {
+                                format!("Failed to read source code from {}", filename)
+                            })?,
+                        )
+                    }
+                };
+
+                let program = trace::span("parse", || options.parse(c, fm.clone(), handler))?;
+
+                let server = match server {
+                    Some(server) => Some(trace::span("server", || {
+                        // The server build has its own diagnostics.
+                        let (res, diagnostics) =
+                            try_with_diagnostics(c.cm.clone(), true, |handler| {
+                                transform_target(
+                                    c,
+                                    fm.clone(),
+                                    program.clone(),
+                                    handler,
+                                    server,
+                                    false,
+                                )
+                            });
+
+                        into_result(res, diagnostics, json_diagnostics)
+                    })?),
+                    None => None,
+                };
+                let (output, metadata) = trace::span("client", || {
+                    transform_target(c, fm, program, handler, options, true)
+                })?;
+
+                Ok((output, metadata, server))
             })
-        }))
-        .map_err(|err| {
-            if let Some(s) = err.downcast_ref::<String>() {
-                anyhow!("failed to process {}", s)
-            } else {
-                anyhow!("failed to process")
-            }
-        });
-
-        match res {
-            Ok(res) => res.convert_err(),
-            Err(err) => Err(napi::Error::new(
-                Status::GenericFailure,
-                format!("{:?}", err),
-            )),
+        })
+    }))
+    .map_err(|err| {
+        if let Some(s) = err.downcast_ref::<String>() {
+            anyhow!("failed to process {}", s)
+        } else {
+            anyhow!("failed to process")
         }
+    })?;
+
+    let (res, server) = match res {
+        Ok((output, metadata, server)) => (Ok((output, metadata)), server),
+        Err(err) => (Err(err), None),
+    };
+    let mut result = into_result(res, diagnostics, json_diagnostics)?;
+    result.server = server.map(Box::new);
+
+    Ok(result)
+}
+
+/// Returns the [TransformResult] of a single target. A failed transform is
+/// only returned as a result if its errors can be reported as
+/// `jsonDiagnostics`.
+fn into_result(
+    res: Result<(TransformOutput, TransformMetadata), Error>,
+    diagnostics: Vec<Diagnostic>,
+    json_diagnostics: bool,
+) -> Result<TransformResult, Error> {
+    match res {
+        Ok((output, metadata)) => Ok(TransformResult {
+            output: Some(output),
+            metadata,
+            diagnostics,
+            server: None,
+        }),
+        Err(_) if json_diagnostics && diagnostics.iter().any(Diagnostic::is_error) => {
+            Ok(TransformResult {
+                output: None,
+                metadata: Default::default(),
+                diagnostics,
+                server: None,
+            })
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Runs [next_swc::custom_before_pass] and swc on a program which has already
+/// been parsed.
+///
+/// The program is the one returned by the parser, so the shared passes run
+/// after the resolver of swc like they do in a single build. Unless
+/// `report_shared`, the diagnostics of the shared passes and of
+/// [TransformOptions::patch] are dropped, as they were already reported for
+/// the other target.
+fn transform_target(
+    c: &Compiler,
+    fm: Arc<SourceFile>,
+    program: Program,
+    handler: &Handler,
+    options: TransformOptions,
+    report_shared: bool,
+) -> Result<(TransformOutput, TransformMetadata), Error> {
+    let muted = Handler::with_emitter(false, false, Box::new(Muted));
+    let shared_handler = if report_shared { handler } else { &muted };
+
+    let options = options.patch(&program, shared_handler);
+
+    let metadata: SharedMetadata = Default::default();
+    let before_pass = chain!(
+        WithHandler(
+            shared_handler,
+            shared_before_pass(c.cm.clone(), fm.clone(), &options, metadata.clone()),
+        ),
+        target_before_pass(c.cm.clone(), fm.clone(), &options, metadata.clone())
+    );
+    let output = process(c, fm, program, handler, &options, before_pass)?;
+
+    Ok((output, metadata.take()))
+}
+
+/// Discards diagnostics which were already reported.
+struct Muted;
+
+impl Emitter for Muted {
+    fn emit(&mut self, _: &DiagnosticBuilder<'_>) {}
+}
+
+/// Runs a pass with [HANDLER] set to another handler.
+struct WithHandler<'a, P>(&'a Handler, P);
+
+impl<P: Fold> Fold for WithHandler<'_, P> {
+    fn fold_program(&mut self, program: Program) -> Program {
+        let WithHandler(handler, pass) = self;
+        HANDLER.set(*handler, || pass.fold_program(program))
+    }
+
+    fn fold_module(&mut self, module: Module) -> Module {
+        let WithHandler(handler, pass) = self;
+        HANDLER.set(*handler, || pass.fold_module(module))
+    }
+
+    fn fold_script(&mut self, script: Script) -> Script {
+        let WithHandler(handler, pass) = self;
+        HANDLER.set(*handler, || pass.fold_script(script))
+    }
+}
+
+/// Runs swc with `before_pass`, recording the Next.js passes, the swc
+/// transforms and codegen as trace spans.
+fn process(
+    c: &Compiler,
+    fm: Arc<SourceFile>,
+    program: Program,
+    handler: &Handler,
+    options: &TransformOptions,
+    before_pass: impl Fold,
+) -> Result<TransformOutput, Error> {
+    // The span of the stage swc is in. Replacing it ends the previous stage.
+    let stage = RefCell::new(None);
+    let enter = |name| {
+        stage.borrow_mut().take();
+        *stage.borrow_mut() = Some(trace::begin(name));
+    };
+
+    let output = c.process_js_with_custom_pass(
+        fm,
+        Some(program),
+        handler,
+        &options.swc,
+        |_| {
+            chain!(
+                trace::hook(|| enter("next-swc-passes")),
+                before_pass,
+                trace::hook(|| enter("swc-transforms"))
+            )
+        },
+        |_| trace::hook(|| enter("codegen")),
+    );
+    drop(stage);
+
+    output
+}
+
+/// Runs [transform_file] with a [Tracer] if the options ask for a trace.
+/// `started` is when the transform was requested.
+fn transform_file_traced(
+    c: &Compiler,
+    input: &Input,
+    options: TransformOptions,
+    server: Option<TransformOptions>,
+    started: Instant,
+) -> Result<TransformResult, Error> {
+    let config = match &options.trace {
+        Some(config) => config.clone(),
+        None => return transform_file(c, input, options, server),
+    };
+
+    let mut tags = HashMap::new();
+    tags.insert("filename".to_string(), options.swc.filename.clone());
+
+    let (result, tracer) = with_tracer(Tracer::new(config, started), || {
+        let _span = trace::begin_with_tags("next-swc-transform", tags);
+        transform_file(c, input, options, server)
+    });
+    // Failing to write the trace should not fail the build.
+    let _ = tracer.finish();
+
+    result
+}
+
+#[derive(Deserialize)]
+pub struct TransformManyItem {
+    /// Overrides `options.filename`.
+    #[serde(default)]
+    pub filename: Option<String>,
+    pub src: String,
+    pub options: TransformOptions,
+}
+
+pub struct TransformManyTask {
+    pub c: Arc<Compiler>,
+    pub items: String,
+    pub started: Instant,
+}
+
+impl Task for TransformManyTask {
+    type Output = Vec<TransformResult>;
+    type JsValue = JsObject;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let items: Vec<TransformManyItem> = deserialize_json(&self.items).convert_err()?;
+        let c = &*self.c;
+        let started = self.started;
+
+        Ok(items
+            .into_par_iter()
+            .map(|item| transform_many_item(c, item, started))
+            .collect())
     }
 
     fn resolve(self, env: Env, result: Self::Output) -> napi::Result<Self::JsValue> {
-        complete_output(&env, result)
+        env.to_js_value(&result)?.coerce_to_object()
+    }
+}
+
+/// Transforms a file of `transformMany`. The errors of a failed transform are
+/// returned as its diagnostics, so a file which fails doesn't fail the others
+/// and every file has the same result.
+fn transform_many_item(c: &Compiler, item: TransformManyItem, started: Instant) -> TransformResult {
+    let mut options = item.options;
+    if let Some(filename) = item.filename {
+        options.swc.filename = filename;
+    }
+    options.json_diagnostics = true;
+
+    let input = Input::Source { src: item.src };
+    match transform_file_traced(c, &input, options, None, started) {
+        Ok(result) => result,
+        Err(err) => TransformResult {
+            output: None,
+            metadata: Default::default(),
+            diagnostics: vec![Diagnostic::error(format!("{:?}", err))],
+            server: None,
+        },
     }
 }
 
@@ -133,7 +476,7 @@ pub fn schedule_transform<F>(cx: CallContext, op: F) -> napi::Result<JsObject>
 where
     F: FnOnce(&Arc<Compiler>, Input, bool, String) -> TransformTask,
 {
-    let c = get_compiler(&cx);
+    let c = get_compiler_at(&cx, 3)?;
 
     let unknown_src = cx.get::<JsUnknown>(0)?;
     let src = match unknown_src.get_type()? {
@@ -165,7 +508,7 @@ pub fn exec_transform<F>(cx: CallContext, op: F) -> napi::Result<JsObject>
 where
     F: FnOnce(&Compiler, String, &TransformOptions) -> Result<Arc<SourceFile>, Error>,
 {
-    let c = get_compiler(&cx);
+    let c = get_compiler_at(&cx, 3)?;
 
     let s = cx.get::<JsString>(0)?.into_utf8()?;
     let is_module = cx.get::<JsBoolean>(1)?;
@@ -196,9 +539,26 @@ pub fn transform(cx: CallContext) -> napi::Result<JsObject> {
         c: c.clone(),
         input,
         options,
+        started: Instant::now(),
     })
 }
 
+/// `transformMany(items, compiler?)` transforms `[{ filename, src, options }]`
+/// in parallel. Results are returned in the same order as `items`.
+#[js_function(2)]
+pub fn transform_many(cx: CallContext) -> napi::Result<JsObject> {
+    let c = get_compiler_at(&cx, 1)?;
+    let items = cx.get_buffer_as_string(0)?;
+
+    cx.env
+        .spawn(TransformManyTask {
+            c,
+            items,
+            started: Instant::now(),
+        })
+        .map(|t| t.promise_object())
+}
+
 #[js_function(4)]
 pub fn transform_sync(cx: CallContext) -> napi::Result<JsObject> {
     exec_transform(cx, |c, src, options| {
@@ -230,3 +590,241 @@ fn test_deserialize_transform_regenerator() {
 
     println!("{:#?}", tr);
 }
+
+#[cfg(test)]
+fn test_options(is_server: bool) -> TransformOptions {
+    serde_json::from_value(serde_json::json!({
+        "jsc": { "parser": { "syntax": "ecmascript", "jsx": true } },
+        "filename": "/some-project/pages/index.js",
+        "isPageFile": true,
+        "isServer": is_server,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_transform_with_server_matches_separate_transforms() {
+    const SRC: &str = r#"
+import fs from 'fs'
+
+export async function getStaticProps() {
+  return { props: { files: fs.readdirSync('.') } }
+}
+
+export default function Home({ files }) {
+  return <div>{files.length}</div>
+}
+"#;
+
+    let c = Compiler::new(Arc::new(swc_common::SourceMap::new(
+        swc_common::FilePathMapping::empty(),
+    )));
+    let input = Input::Source {
+        src: SRC.to_string(),
+    };
+
+    let both = transform_file(&c, &input, test_options(false), Some(test_options(true))).unwrap();
+    let client = transform_file(&c, &input, test_options(false), None).unwrap();
+    let server = transform_file(&c, &input, test_options(true), None).unwrap();
+
+    let both_server = both.server.unwrap();
+    assert_eq!(both.output.unwrap().code, client.output.unwrap().code);
+    assert_eq!(
+        both_server.output.unwrap().code,
+        server.output.unwrap().code
+    );
+}
+
+#[test]
+fn test_transform_with_server_reports_shared_warnings_once() {
+    const SRC: &str = r#"
+export default function Home() {
+  return <div />
+}
+
+module.exports.config = {}
+"#;
+
+    let c = Compiler::new(Arc::new(swc_common::SourceMap::new(
+        swc_common::FilePathMapping::empty(),
+    )));
+    let input = Input::Source {
+        src: SRC.to_string(),
+    };
+
+    let result = transform_file(&c, &input, test_options(false), Some(test_options(true))).unwrap();
+
+    let codes = |diagnostics: &[Diagnostic]| {
+        diagnostics
+            .iter()
+            .map(|d| d.code.clone().unwrap_or_default())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        codes(&result.diagnostics),
+        vec![next_swc::diagnostics::MIXED_MODULE_SYNTAX]
+    );
+    assert_eq!(
+        codes(&result.server.unwrap().diagnostics),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn test_transform_many_returns_a_result_per_file() {
+    let c = Arc::new(Compiler::new(Arc::new(swc_common::SourceMap::new(
+        swc_common::FilePathMapping::empty(),
+    ))));
+    let item = |filename: &str, src: &str| {
+        serde_json::json!({
+            "filename": filename,
+            "src": src,
+            "options": {
+                "jsc": { "parser": { "syntax": "ecmascript" } },
+                "isPageFile": true,
+            },
+        })
+    };
+    let items = serde_json::json!([
+        item("/some-project/pages/a.js", "export default function A() {}"),
+        item("/some-project/pages/b.js", "export * from './c'"),
+        item("/some-project/pages/c.js", "export default ("),
+    ]);
+
+    let results = TransformManyTask {
+        c,
+        items: items.to_string(),
+        started: Instant::now(),
+    }
+    .compute()
+    .unwrap();
+
+    let results = results
+        .into_iter()
+        .map(|result| serde_json::to_value(&result).unwrap())
+        .collect::<Vec<_>>();
+    assert!(results[0]["code"].is_string());
+    assert_eq!(results[0]["diagnostics"], serde_json::json!([]));
+    for failed in &results[1..] {
+        assert!(failed.get("code").is_none());
+        assert!(failed["metadata"].is_object());
+        assert_eq!(failed["diagnostics"][0]["severity"], "error");
+    }
+    assert_eq!(
+        results[1]["diagnostics"][0]["code"],
+        next_swc::diagnostics::EXPORT_ALL_IN_PAGE
+    );
+}
+
+/// Transforms `src` with a trace and returns whether the transform succeeded
+/// and the trace events.
+#[cfg(test)]
+fn transform_traced(name: &str, src: &str) -> (bool, Vec<serde_json::Value>) {
+    let file = std::env::temp_dir().join(format!("next-swc-trace-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&file);
+
+    let mut options = test_options(false);
+    options.trace = Some(
+        serde_json::from_value(serde_json::json!({
+            "file": file,
+            "traceId": "trace",
+            "parentId": 1,
+            "startTime": 1000,
+        }))
+        .unwrap(),
+    );
+    let c = Compiler::new(Arc::new(swc_common::SourceMap::new(
+        swc_common::FilePathMapping::empty(),
+    )));
+    let input = Input::Source {
+        src: src.to_string(),
+    };
+    let result = transform_file_traced(&c, &input, options, None, Instant::now());
+
+    let trace = read_to_string(&file).unwrap();
+    std::fs::remove_file(&file).unwrap();
+    assert_eq!(trace.lines().count(), 1);
+
+    (result.is_ok(), serde_json::from_str(&trace).unwrap())
+}
+
+/// Checks that the events form a single tree below the span of the JS build,
+/// with every span inside its parent, and returns their names.
+#[cfg(test)]
+fn assert_well_formed(events: &[serde_json::Value]) -> Vec<&str> {
+    let by_id: HashMap<u64, &serde_json::Value> = events
+        .iter()
+        .map(|event| (event["id"].as_u64().unwrap(), event))
+        .collect();
+    assert_eq!(by_id.len(), events.len(), "duplicate ids");
+
+    let roots: Vec<_> = events
+        .iter()
+        .filter(|event| event["parentId"] == 1)
+        .collect();
+    assert_eq!(roots.len(), 1);
+    assert_eq!(roots[0]["name"], "next-swc-transform");
+
+    for event in events {
+        if event["parentId"] == 1 {
+            continue;
+        }
+        let parent = by_id[&event["parentId"].as_u64().unwrap()];
+        let (start, end) = (
+            event["timestamp"].as_u64().unwrap(),
+            event["timestamp"].as_u64().unwrap() + event["duration"].as_u64().unwrap(),
+        );
+        let (parent_start, parent_end) = (
+            parent["timestamp"].as_u64().unwrap(),
+            parent["timestamp"].as_u64().unwrap() + parent["duration"].as_u64().unwrap(),
+        );
+        // Timestamps and durations are rounded down separately.
+        assert!(
+            start >= parent_start && end <= parent_end + 1,
+            "{} is not inside {}",
+            event["name"],
+            parent["name"]
+        );
+    }
+
+    events
+        .iter()
+        .map(|event| event["name"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_trace_of_successful_transform() {
+    let (ok, events) = transform_traced(
+        "success",
+        "export default function Home() { return <div /> }",
+    );
+    assert!(ok);
+
+    let names = assert_well_formed(&events);
+    for name in [
+        "parse",
+        "client",
+        "next-swc-passes",
+        "swc-transforms",
+        "codegen",
+        "styled_jsx",
+    ] {
+        assert!(names.contains(&name), "missing {}", name);
+    }
+}
+
+#[test]
+fn test_trace_of_failed_transform() {
+    let (ok, events) = transform_traced("error", "export * from './a'");
+    assert!(!ok);
+
+    let names = assert_well_formed(&events);
+    for name in [
+        "client",
+        "next-swc-passes",
+        "disallow_re_export_all_in_page",
+    ] {
+        assert!(names.contains(&name), "missing {}", name);
+    }
+}
@@ -1,8 +1,14 @@
 use anyhow::{Context, Error};
-use next_swc::{custom_before_pass, TransformOptions};
+use next_swc::{
+    custom_before_pass,
+    diagnostics::{try_with_diagnostics, Diagnostic},
+    metadata::{SharedMetadata, TransformMetadata},
+    TransformOptions,
+};
 use once_cell::sync::Lazy;
+use serde::Serialize;
 use std::sync::Arc;
-use swc::{config::JsMinifyOptions, try_with_handler, Compiler};
+use swc::{config::JsMinifyOptions, try_with_handler, Compiler, TransformOutput};
 use swc_common::{FileName, FilePathMapping, SourceMap};
 use swc_ecmascript::transforms::pass::noop;
 use wasm_bindgen::prelude::*;
@@ -11,6 +17,14 @@ fn convert_err(err: Error) -> JsValue {
     format!("{:?}", err).into()
 }
 
+#[derive(Serialize)]
+struct TransformResult {
+    #[serde(flatten)]
+    output: Option<TransformOutput>,
+    metadata: TransformMetadata,
+    diagnostics: Vec<Diagnostic>,
+}
+
 #[wasm_bindgen(js_name = "minifySync")]
 pub fn minify_sync(s: &str, opts: JsValue) -> Result<JsValue, JsValue> {
     console_error_panic_hook::set_once();
@@ -36,9 +50,13 @@ pub fn transform_sync(s: &str, opts: JsValue) -> Result<JsValue, JsValue> {
 
     let c = compiler();
 
-    try_with_handler(c.cm.clone(), false, |handler| {
-        let opts: TransformOptions = opts.into_serde().context("failed to parse options")?;
+    let opts: TransformOptions = opts
+        .into_serde()
+        .context("failed to parse options")
+        .map_err(convert_err)?;
+    let json_diagnostics = opts.json_diagnostics;
 
+    let (res, diagnostics) = try_with_diagnostics(c.cm.clone(), false, |handler| {
         let fm = c.cm.new_source_file(
             if opts.swc.filename.is_empty() {
                 FileName::Anon
@@ -47,14 +65,34 @@ pub fn transform_sync(s: &str, opts: JsValue) -> Result<JsValue, JsValue> {
             },
             s.into(),
         );
-        let before_pass = custom_before_pass(c.cm.clone(), fm.clone(), &opts);
-        let out = c
+        let metadata: SharedMetadata = Default::default();
+        let before_pass = custom_before_pass(c.cm.clone(), fm.clone(), &opts, metadata.clone());
+        let output = c
             .process_js_with_custom_pass(fm, None, handler, &opts.swc, |_| before_pass, |_| noop())
             .context("failed to process js file")?;
 
-        JsValue::from_serde(&out).context("failed to serialize json")
-    })
-    .map_err(convert_err)
+        Ok((output, metadata.take()))
+    });
+
+    let result = match res {
+        Ok((output, metadata)) => TransformResult {
+            output: Some(output),
+            metadata,
+            diagnostics,
+        },
+        Err(_) if json_diagnostics && diagnostics.iter().any(Diagnostic::is_error) => {
+            TransformResult {
+                output: None,
+                metadata: Default::default(),
+                diagnostics,
+            }
+        }
+        Err(err) => return Err(convert_err(err)),
+    };
+
+    JsValue::from_serde(&result)
+        .context("failed to serialize json")
+        .map_err(convert_err)
 }
 
 /// Get global sourcemap
@@ -69,6 +69,36 @@ async function loadWasm() {
             bindings.transformSync(src.toString(), options)
           )
         },
+        // Same results as the native `transformMany`, transformed one by one.
+        transformMany(items) {
+          return Promise.resolve(
+            items.map(({ filename, src, options }) => {
+              try {
+                return bindings.transformSync(src.toString(), {
+                  ...options,
+                  filename: filename ?? options.filename,
+                  jsonDiagnostics: true,
+                })
+              } catch (error) {
+                return {
+                  metadata: defaultMetadata(),
+                  diagnostics: [
+                    {
+                      code: null,
+                      severity: 'error',
+                      file: null,
+                      start: null,
+                      end: null,
+                      message: String(error),
+                      docsUrl: null,
+                      fixes: [],
+                    },
+                  ],
+                }
+              }
+            })
+          )
+        },
         minify(src, options) {
           return Promise.resolve(bindings.minifySync(src.toString(), options))
         },
@@ -129,7 +159,7 @@ function loadNative() {
   if (bindings) {
     nativeBindings = {
       isWasm: false,
-      transform(src, options) {
+      transform(src, options, compiler) {
         const isModule =
           typeof src !== undefined &&
           typeof src !== 'string' &&
@@ -143,11 +173,12 @@ function loadNative() {
         return bindings.transform(
           isModule ? JSON.stringify(src) : src,
           isModule,
-          toBuffer(options)
+          toBuffer(options),
+          compiler
         )
       },
 
-      transformSync(src, options) {
+      transformSync(src, options, compiler) {
         if (typeof src === undefined) {
           throw new Error(
             "transformSync doesn't implement reading the file from filesystem"
@@ -167,10 +198,23 @@ function loadNative() {
         return bindings.transformSync(
           isModule ? JSON.stringify(src) : src,
           isModule,
-          toBuffer(options)
+          toBuffer(options),
+          compiler
         )
       },
 
+      // Results are returned in the order of `items`. A file which fails to
+      // transform resolves to a result without `code`, whose `diagnostics`
+      // say why, instead of rejecting the batch.
+      transformMany(items, compiler) {
+        return bindings.transformMany(toBuffer(items), compiler)
+      },
+
+      // Each compiler has its own source map, which can be dropped with `reset()`
+      createCompiler() {
+        return new bindings.Compiler()
+      },
+
       minify(src, options) {
         return bindings.minify(toBuffer(src), toBuffer(options ?? {}))
       },
@@ -193,6 +237,19 @@ function loadNative() {
   throw attempts
 }
 
+// The metadata of a file which no pass has reported anything for.
+function defaultMetadata() {
+  return {
+    nextSsg: { isSsg: false, isSsp: false, removed: [], retainedImports: [] },
+    nextDynamic: [],
+    pageConfig: null,
+    styledJsx: false,
+    relay: false,
+    directive: null,
+    unoptimizedBarrels: [],
+  }
+}
+
 function toBuffer(t) {
   return Buffer.from(JSON.stringify(t))
 }
@@ -202,14 +259,24 @@ export async function isWasm() {
   return bindings.isWasm
 }
 
-export async function transform(src, options) {
+export async function transform(src, options, compiler) {
+  let bindings = await loadBindings()
+  return bindings.transform(src, options, compiler)
+}
+
+export function transformSync(src, options, compiler) {
+  let bindings = loadBindingsSync()
+  return bindings.transformSync(src, options, compiler)
+}
+
+export async function transformMany(items, compiler) {
   let bindings = await loadBindings()
-  return bindings.transform(src, options)
+  return bindings.transformMany(items, compiler)
 }
 
-export function transformSync(src, options) {
+export function createCompiler() {
   let bindings = loadBindingsSync()
-  return bindings.transformSync(src, options)
+  return bindings.createCompiler()
 }
 
 export async function minify(src, options) {